@@ -1,12 +1,12 @@
 
-use proc_macro2::{TokenTree, Punct, Span, Delimiter::{Parenthesis, Brace, Bracket}};
+use proc_macro2::{TokenStream, TokenTree, Punct, Group, Ident, Span, Delimiter::{Parenthesis, Brace, Bracket}};
 
-use crate::{*, BlockModifier::{Concat, First, Last, NotFirst, NotLast}};
+use crate::{*, BlockModifier::{Concat, Str, First, Last, NotFirst, NotLast}};
 
 
 pub enum Action {
    Escape(Punct),
-   Assign(String, Assign),
+   Assign(AssignKind, Ident, Assign),
    Quote(Quote),
 }
 
@@ -16,6 +16,136 @@ macro_rules! ok_action {
 }
 
 
+// consume a following `$else` if there is one
+fn next_else(input: &mut TokenIter) -> bool {
+   match (input.next(), input.next()) {
+      (Some(TokenTree::Punct(pt)), Some(TokenTree::Ident(id))) if pt.as_char() == '$' && id == "else" => true,
+      (first, second) => {
+         input.push_in_front(first.into_iter().chain(second).collect::<TokenStream>());
+         false
+      },
+   }
+}
+
+// up to three tokens ending with a brace block, for trailing clauses
+fn lookahead(input: &mut TokenIter) -> Vec<TokenTree> {
+
+   let mut tokens = Vec::new();
+
+   while tokens.len() < 3 {
+      match input.next() {
+         Some(token) => {
+            let brace = matches!(&token, TokenTree::Group(gp) if gp.delimiter() == Brace);
+            tokens.push(token);
+            if brace { break }
+         },
+         None => break,
+      }
+   }
+
+   tokens
+}
+
+// consume a following `sep {..}` or `sep trailing {..}` if there is one
+fn next_separator(input: &mut TokenIter) -> Option<Separator> {
+
+   let tokens = lookahead(input);
+
+   let separator = match tokens.as_slice() {
+      [TokenTree::Ident(sep), TokenTree::Group(blk)] if sep == "sep" && blk.delimiter() == Brace => {
+         Some(Separator { block: blk.clone(), trailing: false })
+      },
+      [TokenTree::Ident(sep), TokenTree::Ident(tr), TokenTree::Group(blk)] if sep == "sep" && tr == "trailing" && blk.delimiter() == Brace => {
+         Some(Separator { block: blk.clone(), trailing: true })
+      },
+      _ => None,
+   };
+
+   if separator.is_none() {
+      input.push_in_front(tokens.into_iter().collect::<TokenStream>());
+   }
+
+   separator
+}
+
+// consume a following `$else {..}` or `$empty {..}` if there is one, plain `else` stays rust code
+fn next_fallback(input: &mut TokenIter) -> Option<Group> {
+
+   let tokens = lookahead(input);
+
+   let fallback = match tokens.as_slice() {
+      [TokenTree::Punct(pt), TokenTree::Ident(id), TokenTree::Group(blk)] if pt.as_char() == '$' && (id == "else" || id == "empty") && blk.delimiter() == Brace => {
+         Some(blk.clone())
+      },
+      _ => None,
+   };
+
+   if fallback.is_none() {
+      input.push_in_front(tokens.into_iter().collect::<TokenStream>());
+   }
+
+   fallback
+}
+
+fn parse_iteration(mut span: Span, modifier: IterModifier, head: Group, input: &mut TokenIter) -> Res<Action> {
+   let blk = match_next!(span, input, Group(blk) if blk.delimiter() == Brace);
+   let separator = next_separator(input);
+   ok_action!(Quote: Quote::Iter(modifier, head, blk, separator, next_fallback(input)))
+}
+
+fn parse_conditional(mut span: Span, cond: Group, input: &mut TokenIter) -> Res<Action> {
+
+   let blk = match_next!(span, input, Group(blk) if blk.delimiter() == Brace);
+
+   let mut branches = vec![(cond, blk)];
+   let mut otherwise = None;
+
+   while next_else(input) {
+      match next!(span, input) {
+         TokenTree::Ident(id) if id == "if" => {
+            let cond = match_next!(span, input, Group(gp) if gp.delimiter() == Parenthesis);
+            let blk = match_next!(span, input, Group(blk) if blk.delimiter() == Brace);
+            branches.push((cond, blk));
+         },
+         TokenTree::Group(blk) if blk.delimiter() == Brace => {
+            otherwise = Some(blk);
+            break;
+         },
+         _ => err!(span, "unexpected token"),
+      }
+   }
+
+   ok_action!(Quote: Quote::If(branches, otherwise))
+}
+
+
+// comma separated identifiers
+fn parse_params(params: Group) -> Res<Vec<Ident>> {
+
+   let mut list = Vec::new();
+   let mut tokens = params.stream().into_iter();
+
+   while let Some(token) = tokens.next() {
+
+      let param = match_token!(token, Ident);
+
+      if list.contains(&param) {
+         err!(param.span(), "duplicate parameter `{}`", param)
+      }
+
+      list.push(param);
+
+      match tokens.next() {
+         None => break,
+         Some(TokenTree::Punct(pt)) if pt.as_char() == ',' => continue,
+         Some(other) => err!(other.span(), "unexpected token"),
+      }
+   }
+
+   Ok(list)
+}
+
+
 pub fn parse_action(mut span: Span, input: &mut TokenIter, env: &mut Env) -> Res<Action> {
 
    match next!(span, input) {
@@ -28,7 +158,35 @@ pub fn parse_action(mut span: Span, input: &mut TokenIter, env: &mut Env) -> Res
 
             // assignment
             TokenTree::Punct(punct) if punct.as_char() == ':' => {
-               ok_action!(Assign: id, parse_assign_value(span, input, env)?)
+               ok_action!(Assign: AssignKind::Local, ident, parse_assign_value(span, input, env)?)
+            },
+
+            // append to a list or insert into a map
+            TokenTree::Punct(punct) if punct.as_char() == '+' => {
+               match_next!(span, input, Punct(pt) if pt.as_char() == ':');
+               ok_action!(Assign: AssignKind::Append, ident, parse_assign_value(span, input, env)?)
+            },
+
+            // scoped assignment
+            TokenTree::Ident(name) if matches!(id.as_str(), "outer" | "let" | "set" | "global") => {
+               match_next!(span, input, Punct(pt) if pt.as_char() == ':');
+               let kind = match id.as_str() {
+                  "outer" => AssignKind::Outer, "let" => AssignKind::Let, "set" => AssignKind::Set, _ => AssignKind::Global,
+               };
+               ok_action!(Assign: kind, name, parse_assign_value(span, input, env)?)
+            },
+
+            // template definition
+            TokenTree::Ident(name) if id == "def" => {
+               let params = match_next!(span, input, Group(gp) if gp.delimiter() == Parenthesis);
+               let body = match_next!(span, input, Group(blk) if blk.delimiter() == Brace);
+               ok_action!(Assign: AssignKind::Local, name.clone(), Assign::Def(name, parse_params(params)?, body))
+            },
+
+            // template call
+            TokenTree::Punct(punct) if punct.as_char() == '!' => {
+               let args = match_next!(span, input, Group(gp) if gp.delimiter() == Parenthesis);
+               ok_action!(Quote: Quote::Call(ident, args))
             },
 
             // modified block quotes
@@ -36,13 +194,40 @@ pub fn parse_action(mut span: Span, input: &mut TokenIter, env: &mut Env) -> Res
                "first" => ok_action!(Quote: Quote::Block(First, blk)),
                "last" => ok_action!(Quote: Quote::Block(Last, blk)),
                "concat_ident" => ok_action!(Quote: Quote::Block(Concat, blk)),
-               _ => err!(ident.span(), "unknown modifier"),
+               "str" => ok_action!(Quote: Quote::Block(Str(StrKind::Plain), blk)),
+               "raw_str" => ok_action!(Quote: Quote::Block(Str(StrKind::Raw), blk)),
+               "byte_str" => ok_action!(Quote: Quote::Block(Str(StrKind::Byte), blk)),
+               "stringify" => ok_action!(Quote: Quote::Block(Str(StrKind::Stringify), blk)),
+               name => match Case::from_name(name) {
+                  Some(case) => ok_action!(Quote: Quote::Block(BlockModifier::Case(case), blk)),
+                  None => Err(
+                     Error::new(ident.span(), format!("unknown block modifier `{}`", id))
+                     .help("expected one of `first`, `last`, `concat_ident`, `str`, `raw_str`, `byte_str`, `stringify`, `snake`, `upper_snake`, `camel`, `pascal` or `kebab`")
+                  ),
+               },
+            },
+
+            // modified iteration
+            TokenTree::Group(gp) if gp.delimiter() == Bracket => match id.as_str() {
+               "sorted" => parse_iteration(span, IterModifier::Sorted, gp, input),
+               _ => Err(
+                  Error::new(ident.span(), format!("unknown iteration modifier `{}`", id))
+                  .help("expected `sorted`")
+               ),
             },
 
             // item len function
             TokenTree::Group(gp) if gp.delimiter() == Parenthesis => match id.as_str() {
                "len" => ok_action!(Quote: Quote::Item(ItemModifier::Len, gp)),
-               _ => err!(ident.span(), "unknown modifier"),
+               "if" => parse_conditional(span, gp, input),
+               "stringify" => ok_action!(Quote: Quote::Item(ItemModifier::Stringify, gp)),
+               name => match Case::from_name(name) {
+                  Some(case) => ok_action!(Quote: Quote::Item(ItemModifier::Case(case), gp)),
+                  None => Err(
+                     Error::new(ident.span(), format!("unknown item modifier `{}`", id))
+                     .help("expected one of `len`, `if`, `stringify`, `snake`, `upper_snake`, `camel`, `pascal` or `kebab`")
+                  ),
+               },
             },
 
             _ => err!(span, "unexpected token"),
@@ -54,6 +239,12 @@ pub fn parse_action(mut span: Span, input: &mut TokenIter, env: &mut Env) -> Res
          // escaped
          '$' => ok_action!(Escape: punct),
 
+         // expression
+         '=' => {
+            let expr = match_next!(span, input, Group(gp) if gp.delimiter() == Parenthesis);
+            ok_action!(Quote: Quote::Expr(expr))
+         },
+
          // concat function
          '#' => {
             let blk = match_next!(span, input, Group(blk) if blk.delimiter() == Brace);
@@ -62,11 +253,11 @@ pub fn parse_action(mut span: Span, input: &mut TokenIter, env: &mut Env) -> Res
 
          // not modifier
          '!' => {
-            let before = span.clone();
+            let before = span;
             let id = match_next!(span, input, Ident(ident)).to_string();
 
             if id != "first" && id != "last" {
-               err!(before.join(span).unwrap(), "unknown modifier")
+               err!(join(before, span), "unknown modifier `!{}`, expected `!first` or `!last`", id)
             }
 
             let blk = match_next!(span, input, Group(blk) if blk.delimiter() == Brace);
@@ -88,10 +279,7 @@ pub fn parse_action(mut span: Span, input: &mut TokenIter, env: &mut Env) -> Res
          Parenthesis => ok_action!(Quote: Quote::Item(ItemModifier::None, gp)),
 
          // bind block quote
-         Bracket => {
-            let blk = match_next!(span, input, Group(blk) if blk.delimiter() == Brace);
-            ok_action!(Quote: Quote::Iter(gp, blk))
-         },
+         Bracket => parse_iteration(span, IterModifier::None, gp, input),
 
          _ => err!(span, "unexpected token"),
       },
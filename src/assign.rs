@@ -1,11 +1,13 @@
 
 use proc_macro2::{TokenTree, Ident, Literal, Group, Span, Delimiter::{Parenthesis, Brace}};
-use std::collections::HashMap;
 use std::rc::Rc;
+use indexmap::IndexMap;
 
 use crate::*;
 
 
+pub enum AssignKind { Local, Let, Set, Global, Outer, Append }
+
 pub enum Assign {
    Ident(Ident),
    Literal(Literal),
@@ -13,6 +15,7 @@ pub enum Assign {
    Item(Group),
    List(Group),
    Map(Group),
+   Def(Ident, Vec<Ident>, Group),
 }
 
 impl Assign {
@@ -20,6 +23,7 @@ impl Assign {
       match self {
          Assign::Ident(tk) => tk.span(), Assign::Literal(tk) => tk.span(),
          Assign::Stream(gp) | Assign::Item(gp) | Assign::List(gp) | Assign::Map(gp) => gp.span(),
+         Assign::Def(_, _, body) => body.span(),
       }
    }
 }
@@ -34,9 +38,9 @@ pub fn parse_assign_value(mut span: Span, input: &mut TokenIter, env: &mut Env)
 
       TokenTree::Group(gp) if gp.delimiter() == Brace => {
          let mut tokens = gp.stream().into_iter();
-         match next!(span, tokens) {
+         match tokens.next() {
             // quoted tokenstream
-            TokenTree::Group(mut blk) if blk.delimiter() == Brace => match tokens.next() {
+            Some(TokenTree::Group(mut blk)) if blk.delimiter() == Brace => match tokens.next() {
                None => {
                   blk.set_span(span);
                   Assign::Stream(blk)
@@ -48,9 +52,21 @@ pub fn parse_assign_value(mut span: Span, input: &mut TokenIter, env: &mut Env)
          }
       },
 
-      TokenTree::Punct(pt) if pt.as_char() == '@' => Assign::Item(
-         match_next!(span, input, Group(gp) if gp.delimiter() == Parenthesis)
-      ),
+      TokenTree::Punct(pt) if pt.as_char() == '@' => Assign::Item(match next!(span, input) {
+
+         TokenTree::Group(gp) if gp.delimiter() == Parenthesis => gp,
+
+         // function call, evaluated as item path
+         TokenTree::Ident(func) => {
+            let args = match_next!(span, input, Group(gp) if matches!(gp.delimiter(), Parenthesis | Brace));
+            let path_span = join(pt.span(), args.span());
+            let mut path = Group::new(Parenthesis, TokenStream::from_iter([pt.into(), func.into(), TokenTree::from(args)]));
+            path.set_span(path_span);
+            path
+         },
+
+         _ => err!(span, "unexpected token"),
+      }),
 
       TokenTree::Punct(pt) if pt.as_char() == '$' => match parse_action(span, input, env)? {
 
@@ -62,7 +78,7 @@ pub fn parse_assign_value(mut span: Span, input: &mut TokenIter, env: &mut Env)
          }
 
          Action::Escape(escape) => err!(escape.span(), "unexpected token"),
-         Action::Assign(_, assign) => err!(span.join(assign.span()).unwrap(), "unexpected assignment"),
+         Action::Assign(_, _, assign) => err!(join(span, assign.span()), "unexpected assignment"),
       },
 
       _ => err!(span, "unexpected token"),
@@ -71,7 +87,7 @@ pub fn parse_assign_value(mut span: Span, input: &mut TokenIter, env: &mut Env)
 
 
 
-fn evaluate_scoped_block(input: TokenStream, env: &mut Env) -> Res<TokenStream> {
+pub fn evaluate_scoped_block(input: TokenStream, env: &mut Env) -> Res<TokenStream> {
    let mut output = TokenStream::new();
    env.push_scope(None);
    let res = parse_block(input, &mut output, env);
@@ -123,7 +139,7 @@ pub fn parse_assign(assign: Assign, env: &mut Env) -> Res<Rc<Item>> {
 
          let stream = evaluate_scoped_block(group.stream(), env)?;
 
-         let mut map = HashMap::new();
+         let mut map = IndexMap::new();
 
          if !stream.is_empty() {
             let mut tokens: TokenIter = stream.into();
@@ -139,7 +155,7 @@ pub fn parse_assign(assign: Assign, env: &mut Env) -> Res<Rc<Item>> {
                let assign = parse_assign_value(group.span(), &mut tokens, env)?;
                let item = parse_assign(assign, env)?;
 
-               map.insert(ident, item);
+               map.insert(ident, item); // a repeated key overwrites the value in place
 
                match tokens.next() {
                   None => break,
@@ -152,5 +168,72 @@ pub fn parse_assign(assign: Assign, env: &mut Env) -> Res<Rc<Item>> {
          Item::Map(map).into()
       },
 
+      Assign::Def(name, params, body) => Item::Def(Def { name, params, body, scope: env.local_items() }).into(),
+
    })
 }
+
+
+// bind the item according to the kind of assignment
+pub fn assign_item(kind: AssignKind, ident: Ident, item: Rc<Item>, env: &mut Env) -> Res<()> {
+
+   let key = ident.to_string();
+
+   match kind {
+
+      AssignKind::Local => {
+         if env.is_shadowing(&key) {
+            env.warn(ident.span(), format!(
+               "`{}` shadows an outer binding, use `$set {}: ...` to update it or `$let {}: ...` to shadow it explicitly",
+               key, key, key,
+            ));
+         }
+         env.set_item(key, item);
+      },
+
+      AssignKind::Let => env.set_item(key, item),
+
+      AssignKind::Global => env.set_global_item(key, item),
+
+      AssignKind::Set => if !env.rebind_item(&key, item, 0) {
+         return Err(
+            Error::new(ident.span(), format!("can't set undefined item `{}`", key))
+            .help(format!("define it with `${}: ...` or `$global {}: ...`", key, key))
+         )
+      },
+
+      AssignKind::Outer => if !env.rebind_item(&key, item, 1) {
+         err!(ident.span(), "item `{}` is not defined in an enclosing scope", key)
+      },
+
+      AssignKind::Append => {
+
+         let existing = match env.find_item(&key) {
+            Some(existing) => existing,
+            None => return Err(
+               Error::new(ident.span(), format!("item `{}` not found", key))
+               .help(format!("define it first, e.g. `${}: ()`", key))
+            ),
+         };
+
+         let updated = match (existing.as_ref(), item.as_ref()) {
+            (Item::List(list), _) => {
+               let mut list = list.clone();
+               list.push(item);
+               Item::List(list)
+            },
+            (Item::Map(map), Item::Map(entries)) => {
+               let mut map = map.clone();
+               map.extend(entries.iter().map(|(key, item)| (key.clone(), Rc::clone(item))));
+               Item::Map(map)
+            },
+            (Item::Map(_), other) => err!(ident.span(), "can only insert a map into map `{}`, found a {} item", key, other.kind()),
+            (other, _) => err!(ident.span(), "can't append to a {} item", other.kind()),
+         };
+
+         env.rebind_item(&key, updated.into(), 0);
+      },
+   }
+
+   Ok(())
+}
@@ -0,0 +1,73 @@
+#[derive(Clone, Copy)]
+pub enum Case { Snake, UpperSnake, Camel, Pascal, Kebab }
+
+
+impl Case {
+
+   pub fn from_name(name: &str) -> Option<Case> {
+      Some(match name {
+         "snake" => Case::Snake,
+         "upper_snake" => Case::UpperSnake,
+         "camel" => Case::Camel,
+         "pascal" => Case::Pascal,
+         "kebab" => Case::Kebab,
+         _ => return None,
+      })
+   }
+
+   // tokens are split separately, so `foo bar` gives two words
+   pub fn convert(self, parts: &[String]) -> String {
+
+      let words: Vec<String> = parts.iter().flat_map(|part| split_words(part)).collect();
+
+      match self {
+         Case::Snake => words.iter().map(|w| w.to_lowercase()).collect::<Vec<_>>().join("_"),
+         Case::UpperSnake => words.iter().map(|w| w.to_uppercase()).collect::<Vec<_>>().join("_"),
+         Case::Kebab => words.iter().map(|w| w.to_lowercase()).collect::<Vec<_>>().join("-"),
+         Case::Pascal => words.iter().map(|w| capitalize(w)).collect(),
+         Case::Camel => words.iter().enumerate().map(|(i, w)| {
+            if i == 0 { w.to_lowercase() } else { capitalize(w) }
+         }).collect(),
+      }
+   }
+}
+
+
+fn capitalize(word: &str) -> String {
+   let mut chars = word.chars();
+   match chars.next() {
+      Some(first) => first.to_uppercase().chain(chars.flat_map(char::to_lowercase)).collect(),
+      None => String::new(),
+   }
+}
+
+
+// split at separators and case changes, keeping acronyms together: `HTTPServer2Go` -> `HTTP Server2 Go`
+fn split_words(input: &str) -> Vec<String> {
+
+   let input = input.strip_prefix("r#").unwrap_or(input); // raw identifier
+
+   let chars: Vec<char> = input.chars().collect();
+   let mut words = Vec::new();
+   let mut word = String::new();
+
+   for (i, &ch) in chars.iter().enumerate() {
+
+      if !ch.is_alphanumeric() {
+         if !word.is_empty() { words.push(std::mem::take(&mut word)); }
+         continue;
+      }
+
+      if let Some(prev) = word.chars().last() {
+         let next_lower = chars.get(i + 1).is_some_and(|c| c.is_lowercase());
+         let boundary = ch.is_uppercase() && (!prev.is_uppercase() || next_lower);
+         if boundary { words.push(std::mem::take(&mut word)); }
+      }
+
+      word.push(ch);
+   }
+
+   if !word.is_empty() { words.push(word); }
+
+   words
+}
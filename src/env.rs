@@ -1,9 +1,10 @@
 
-use proc_macro2::{TokenStream, Span, Ident, Literal};
+use proc_macro2::{TokenStream, Group, Span, Ident, Literal};
 use std::collections::HashMap;
 use std::rc::Rc;
+use indexmap::IndexMap;
 
-use crate::Res;
+use crate::{Res, Error, join, int_literal};
 
 
 // item env
@@ -12,10 +13,44 @@ pub enum Item {
    Literal(Literal),
    Stream(TokenStream),
    List(Vec<Rc<Item>>),
-   Map(HashMap<String, Rc<Item>>),
+   Map(IndexMap<String, Rc<Item>>), // keeps insertion order
+   Range(Range),
+   Def(Def),
 }
 
-pub struct Env(Vec<Scope>);
+// template definition, evaluated when called
+pub struct Def {
+   pub name: Ident,
+   pub params: Vec<Ident>,
+   pub body: Group,
+   pub scope: HashMap<String, Rc<Item>>, // bindings at the definition outside the root scope
+}
+
+// integer range, evaluated lazily
+pub struct Range {
+   pub start: i128,
+   pub step: i128,
+   pub len: usize,
+   pub suffix: String,
+}
+
+impl Range {
+   pub fn get(&self, index: usize) -> Option<Literal> {
+      if index < self.len { Some(int_literal(self.start + self.step * index as i128, &self.suffix)) }
+      else { None }
+   }
+
+   pub fn iter(&self) -> impl ExactSizeIterator<Item=Literal> + '_ {
+      (0..self.len).map(|i| self.get(i).unwrap()) // in bounds
+   }
+}
+
+pub struct Env {
+   scopes: Vec<Scope>,
+   calls: usize, // nested template calls
+   files: Vec<String>, // included files, tracked for rebuilds
+   warnings: Vec<(Span, String)>,
+}
 
 
 // scope
@@ -39,62 +74,181 @@ impl Scope {
 }
 
 
-// getter key
+// getter key, negative indices count from the end
 pub enum Key {
    String(String),
-   Index(usize)
+   Index(isize),
+   Slice(Option<isize>, Option<isize>), // exclusive end
+}
+
+fn resolve_index(index: isize, len: usize) -> Option<usize> {
+   let index = if index < 0 { len as isize + index } else { index };
+   if index >= 0 && (index as usize) < len { Some(index as usize) } else { None }
+}
+
+// slice bounds are clamped to the length
+fn resolve_slice(start: Option<isize>, end: Option<isize>, len: usize) -> (usize, usize) {
+   let bound = |bound: isize| if bound < 0 { (len as isize + bound).max(0) as usize } else { (bound as usize).min(len) };
+   let start = start.map_or(0, bound);
+   (start, end.map_or(len, bound).max(start))
 }
 
 
 pub struct Segment { pub span: Span, pub key: Key }
 
 
+// scalars compare by their tokens, so `{u8}` equals the ident `u8`
+impl PartialEq for Item {
+   fn eq(&self, other: &Self) -> bool {
+      match (self, other) {
+         (Item::List(a), Item::List(b)) => a == b,
+         (Item::Map(a), Item::Map(b)) => a == b,
+         (Item::Range(a), Item::Range(b)) => a.iter().map(|lit| lit.to_string()).eq(b.iter().map(|lit| lit.to_string())),
+         (Item::List(_) | Item::Map(_) | Item::Range(_) | Item::Def(_), _) => false,
+         (_, Item::List(_) | Item::Map(_) | Item::Range(_) | Item::Def(_)) => false,
+         (a, b) => a.to_token_string() == b.to_token_string(),
+      }
+   }
+}
+
+
 impl Item {
-   pub fn get_item(self: &Rc<Self>, mut span: Span, path: &[Segment]) -> Res<Rc<Item>> {
 
-      let iter = path.into_iter();
-      let mut last_item = self;
+   pub fn kind(&self) -> &'static str {
+      match self {
+         Item::Ident(_) => "identifier",
+         Item::Literal(_) => "literal",
+         Item::Stream(_) => "token stream",
+         Item::List(_) => "list",
+         Item::Map(_) => "map",
+         Item::Range(_) => "range",
+         Item::Def(_) => "template",
+      }
+   }
+
+   pub fn to_token_string(&self) -> String {
+      match self {
+         Item::Ident(ident) => ident.to_string(),
+         Item::Literal(lit) => lit.to_string(),
+         Item::Stream(stream) => stream.to_string(),
+         Item::List(_) | Item::Map(_) | Item::Range(_) | Item::Def(_) => String::new(),
+      }
+   }
+
+   pub fn len(&self) -> usize {
+      match self {
+         Item::Ident(_) | Item::Literal(_) | Item::Stream(_) | Item::Def(_) => 1,
+         Item::List(list) => list.len(),
+         Item::Map(map) => map.len(),
+         Item::Range(range) => range.len,
+      }
+   }
+
+   pub fn is_empty(&self) -> bool {
+      match self {
+         Item::Ident(_) | Item::Literal(_) | Item::Def(_) => false,
+         Item::Stream(stream) => stream.is_empty(),
+         Item::List(list) => list.is_empty(),
+         Item::Map(map) => map.is_empty(),
+         Item::Range(range) => range.len == 0,
+      }
+   }
+
+   // `false`, `0` and empty items are falsy
+   pub fn is_truthy(&self) -> bool {
+      match self {
+         Item::Ident(ident) => ident != "false",
+         Item::Literal(lit) => lit.to_string() != "0",
+         _ => !self.is_empty(),
+      }
+   }
+
+   // missing keys and indices give `None` if optional, other errors remain
+   pub fn lookup(self: &Rc<Self>, mut span: Span, path: &[Segment], optional: bool) -> Res<Option<Rc<Item>>> {
+
+      let iter = path.iter();
+      let mut last_item = Rc::clone(self);
 
       for segm in iter {
-         match last_item.as_ref() {
+         last_item = match last_item.as_ref() {
 
-            Item::Ident(_) | Item::Literal(_) | Item::Stream(_) => {
-               err!(span, "item is not indexable")
+            Item::Ident(_) | Item::Literal(_) | Item::Stream(_) | Item::Def(_) => {
+               err!(span, "{} item is not indexable", last_item.kind())
             },
 
             Item::List(list) => { match segm.key {
-               Key::String(_) => {
-                  err!(segm.span, "can't index list with an identifier")
+               Key::String(ref key) => {
+                  err!(segm.span, "can't index list with identifier `{}`", key)
+               },
+               Key::Slice(start, end) => {
+                  let (start, end) = resolve_slice(start, end, list.len());
+                  Item::List(list[start..end].to_vec()).into()
                },
                Key::Index(index) => {
-                  if let Some(item) = list.get(index) {
-                     last_item = item;
+                  if let Some(item) = resolve_index(index, list.len()).map(|index| &list[index]) {
+                     Rc::clone(item)
                   }
+                  else if optional { return Ok(None) }
                   else {
-                     err!(segm.span, "item not found")
+                     return Err(
+                        Error::new(segm.span, format!("index {} is out of bounds", index))
+                        .note(format!("the list has {} items", list.len()))
+                     )
                   }
                },
             }},
 
             Item::Map(map) => { match segm.key {
-               Key::Index(_) => {
-                  err!(segm.span, "can't index map with an integer")
+               Key::Index(index) => {
+                  err!(segm.span, "can't index map with integer `{}`", index)
+               },
+               Key::Slice(_, _) => {
+                  err!(segm.span, "can't slice a map")
                },
                Key::String(ref key) => {
                   if let Some(item) = map.get(key) {
-                     last_item = item;
+                     Rc::clone(item)
                   }
+                  else if optional { return Ok(None) }
                   else {
-                     err!(segm.span, "item not found")
+                     return Err(
+                        Error::new(segm.span, format!("key `{}` not found", key))
+                        .candidates(map.keys())
+                     )
                   }
                },
             }},
-         }
 
-         span = span.join(segm.span).unwrap(); // should never fail
+            Item::Range(range) => { match segm.key {
+               Key::String(ref key) => {
+                  err!(segm.span, "can't index range with identifier `{}`", key)
+               },
+               Key::Slice(start, end) => {
+                  let (start, end) = resolve_slice(start, end, range.len);
+                  Item::Range(Range {
+                     start: range.start + range.step * start as i128, step: range.step,
+                     len: end - start, suffix: range.suffix.clone(),
+                  }).into()
+               },
+               Key::Index(index) => {
+                  if let Some(lit) = resolve_index(index, range.len).and_then(|index| range.get(index)) {
+                     Item::Literal(lit).into()
+                  }
+                  else if optional { return Ok(None) }
+                  else {
+                     return Err(
+                        Error::new(segm.span, format!("index {} is out of bounds", index))
+                        .note(format!("the range has {} items", range.len))
+                     )
+                  }
+               },
+            }},
+         };
+
+         span = join(span, segm.span);
       }
 
-      Ok(Rc::clone(last_item))
+      Ok(Some(last_item))
    }
 }
 
@@ -103,41 +257,122 @@ impl Item {
 impl Env {
 
    pub fn new() -> Self {
-      Env(Vec::with_capacity(2))
+      Env { scopes: Vec::with_capacity(2), calls: 0, files: Vec::new(), warnings: Vec::new() }
    }
 
    pub fn push_scope(&mut self, iter_scope: Option<IterScope>) {
-      self.0.push(Scope::new(iter_scope));
+      self.scopes.push(Scope::new(iter_scope));
    }
 
    pub fn pop_scope(&mut self) {
-      self.0.pop();
+      self.scopes.pop();
+   }
+
+   // bindings visible outside the root scope, inner ones win
+   pub fn local_items(&self) -> HashMap<String, Rc<Item>> {
+      self.scopes.iter().skip(1).flat_map(|scope| scope.items.iter()).map(|(key, item)| (key.clone(), Rc::clone(item))).collect()
+   }
+
+   // the callee sees the root scope and the given items, not the caller's locals or iterations
+   pub fn with_call_scope<T>(&mut self, items: HashMap<String, Rc<Item>>, f: impl FnOnce(&mut Self) -> T) -> T {
+      let caller = self.scopes.split_off(1); // the root scope always exists
+      self.scopes.push(Scope { items, iter_scope: None });
+      self.calls += 1;
+      let res = f(self);
+      self.calls -= 1;
+      self.scopes.truncate(1);
+      self.scopes.extend(caller);
+      res
+   }
+
+   pub fn calls(&self) -> usize {
+      self.calls
    }
 
    pub fn set_item(&mut self, key: String, item: Rc<Item>) {
-      self.0.last_mut().unwrap().items.insert(key, item); // should never fail
+      self.scopes.last_mut().unwrap().items.insert(key, item); // should never fail
+   }
+
+   pub fn set_global_item(&mut self, key: String, item: Rc<Item>) {
+      self.scopes[0].items.insert(key, item); // the root scope always exists
+   }
+
+   // bound in an enclosing scope but not in the innermost one
+   pub fn is_shadowing(&self, key: &str) -> bool {
+      match self.scopes.split_last() {
+         Some((last, outer)) => !last.items.contains_key(key) && outer.iter().any(|scope| scope.items.contains_key(key)),
+         None => false,
+      }
+   }
+
+   // repeated warnings, e.g. from iterations, are reported once
+   pub fn warn(&mut self, span: Span, message: String) {
+      let id = format!("{:?}", span);
+      if !self.warnings.iter().any(|(other, msg)| format!("{:?}", other) == id && *msg == message) {
+         self.warnings.push((span, message));
+      }
+   }
+
+   pub fn warnings(&self) -> &[(Span, String)] {
+      &self.warnings
+   }
+
+   // replace the nearest binding of `key`, skipping the innermost `skip` scopes
+   pub fn rebind_item(&mut self, key: &str, item: Rc<Item>, skip: usize) -> bool {
+      match self.scopes.iter_mut().rev().skip(skip).find(|scope| scope.items.contains_key(key)) {
+         Some(scope) => { scope.items.insert(key.to_string(), item); true },
+         None => false,
+      }
+   }
+
+   pub fn find_item(&self, key: &str) -> Option<Rc<Item>> {
+      self.scopes.iter().rev().find_map(|scope| scope.items.get(key)).cloned()
+   }
+
+   pub fn track_file(&mut self, path: String) {
+      if !self.files.contains(&path) { self.files.push(path) }
+   }
+
+   pub fn tracked_files(&self) -> &[String] {
+      &self.files
    }
 
    pub fn get_iter_scope(&self) -> Option<&IterScope> {
-      self.0.iter().rev().find_map(|scope| scope.iter_scope.as_ref())
+      self.get_outer_iter_scope(0)
+   }
+
+   // enclosing iteration scope, `0` is the innermost
+   pub fn get_outer_iter_scope(&self, level: usize) -> Option<&IterScope> {
+      self.scopes.iter().rev().filter_map(|scope| scope.iter_scope.as_ref()).nth(level)
    }
 
    pub fn get_item(&self, path: &[Segment]) -> Res<Rc<Item>> {
+      Ok(self.lookup(path, false)?.unwrap()) // only missing if optional
+   }
+
+   pub fn lookup(&self, path: &[Segment], optional: bool) -> Res<Option<Rc<Item>>> {
 
       let first = &path[0]; // should never fail
 
-      let span = first.span.clone();
+      let span = first.span;
 
       let key = match first.key {
          Key::String(ref key) => key,
-         Key::Index(_) => err!(span, "can't index scope with an integer"),
+         Key::Index(index) => err!(span, "can't index scope with integer `{}`", index),
+         Key::Slice(_, _) => err!(span, "can't slice the scope"),
       };
 
-      let item = self.0.iter().rev()
-         .find_map(|scope| scope.items.get(key))
-         .ok_or_else(|| (span, "item not found"))?
-      ;
+      let item = match self.scopes.iter().rev().find_map(|scope| scope.items.get(key)) {
+         Some(item) => item,
+         None if optional => return Ok(None),
+         None => {
+            let mut names: Vec<_> = self.scopes.iter().flat_map(|scope| scope.items.keys()).collect();
+            names.sort();
+            names.dedup();
+            return Err(Error::new(span, format!("item `{}` not found", key)).candidates(names))
+         },
+      };
 
-      item.get_item(span, &path[1..])
+      item.lookup(span, &path[1..], optional)
    }
 }
@@ -0,0 +1,101 @@
+use proc_macro2::{TokenStream, Span, Literal};
+use quote::quote_spanned;
+
+
+struct Note { span: Option<Span>, message: String }
+
+struct Diagnostic {
+   span: Span,
+   message: String,
+   notes: Vec<Note>,
+}
+
+// one or more diagnostics, emitted together
+pub struct Error(Vec<Diagnostic>);
+
+
+// syn errors may hold several messages
+impl From<syn::Error> for Error {
+   fn from(err: syn::Error) -> Self {
+      Error(err.into_iter().map(|err| Diagnostic { span: err.span(), message: err.to_string(), notes: Vec::new() }).collect())
+   }
+}
+
+
+impl Error {
+
+   pub fn new(span: Span, message: impl Into<String>) -> Self {
+      Error(vec![Diagnostic { span, message: message.into(), notes: Vec::new() }])
+   }
+
+   pub fn empty() -> Self {
+      Error(Vec::new())
+   }
+
+   pub fn is_empty(&self) -> bool {
+      self.0.is_empty()
+   }
+
+   fn add_note(mut self, span: Option<Span>, message: String) -> Self {
+      if let Some(diag) = self.0.last_mut() {
+         diag.notes.push(Note { span, message });
+      }
+      self
+   }
+
+   // notes attach to the last diagnostic
+   pub fn note(self, message: impl Into<String>) -> Self {
+      self.add_note(None, format!("note: {}", message.into()))
+   }
+
+   pub fn note_at(self, span: Span, message: impl Into<String>) -> Self {
+      self.add_note(Some(span), format!("note: {}", message.into()))
+   }
+
+   pub fn help(self, message: impl Into<String>) -> Self {
+      self.add_note(None, format!("help: {}", message.into()))
+   }
+
+   pub fn candidates<'a>(self, names: impl IntoIterator<Item=&'a String>) -> Self {
+      let names: Vec<_> = names.into_iter().map(|name| format!("`{}`", name)).collect();
+      if names.is_empty() { self }
+      else { self.note(format!("available: {}", names.join(", "))) }
+   }
+
+   pub fn combine(&mut self, other: Error) {
+      self.0.extend(other.0);
+   }
+
+   pub fn into_result(self) -> Result<(), Error> {
+      if self.is_empty() { Ok(()) } else { Err(self) }
+   }
+
+   // one compile_error! per diagnostic, spanned notes get their own
+   pub fn to_compile_errors(&self) -> TokenStream {
+
+      let mut output = TokenStream::new();
+
+      for diag in &self.0 {
+
+         let mut message = diag.message.clone();
+
+         for note in diag.notes.iter().filter(|note| note.span.is_none()) {
+            message.push('\n');
+            message.push_str(&note.message);
+         }
+
+         let lit = Literal::string(&message);
+         output.extend(quote_spanned!{diag.span=> compile_error!{#lit}});
+
+         for note in &diag.notes {
+            if let Some(span) = note.span {
+               let lit = Literal::string(&note.message);
+               output.extend(quote_spanned!{span=> compile_error!{#lit}});
+            }
+         }
+      }
+
+      output
+   }
+}
+
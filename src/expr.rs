@@ -0,0 +1,339 @@
+use proc_macro2::{TokenStream, TokenTree, Group, Ident, Literal, Span, Spacing, Delimiter::{Parenthesis, Brace, None as NoDelim}};
+use std::rc::Rc;
+
+use crate::*;
+
+
+// lexed expression tokens
+enum Lex {
+   Op(String, Span),
+   Operand(Operand),
+}
+
+enum Operand {
+   Path(Group),
+   Literal(Literal),
+   Call(Ident, Group),
+   Paren(Group),
+   Stream(Group),
+}
+
+impl Operand {
+   fn span(&self) -> Span {
+      match self {
+         Operand::Literal(lt) => lt.span(),
+         Operand::Call(id, gp) => join(id.span(), gp.span()),
+         Operand::Path(gp) | Operand::Paren(gp) | Operand::Stream(gp) => gp.span(),
+      }
+   }
+}
+
+
+// expression tree
+#[derive(Clone, Copy)]
+enum BinOp { Or, And, Eq, Ne, Lt, Le, Gt, Ge, Add, Sub, Mul, Div, Rem }
+
+enum Expr {
+   Operand(Operand),
+   Not(Span, Box<Expr>),
+   Neg(Span, Box<Expr>),
+   Binary(BinOp, Span, Box<Expr>, Box<Expr>),
+}
+
+
+// operator precedence, lowest first
+fn binary_op(op: &str) -> Option<(u8, BinOp)> {
+   Some(match op {
+      "||" => (0, BinOp::Or),
+      "&&" => (1, BinOp::And),
+      "==" => (2, BinOp::Eq), "!=" => (2, BinOp::Ne),
+      "<" => (2, BinOp::Lt), "<=" => (2, BinOp::Le), ">" => (2, BinOp::Gt), ">=" => (2, BinOp::Ge),
+      "+" => (3, BinOp::Add), "-" => (3, BinOp::Sub),
+      "*" => (4, BinOp::Mul), "/" => (4, BinOp::Div), "%" => (4, BinOp::Rem),
+      _ => return None,
+   })
+}
+
+const JOINED_OPS: [&str; 6] = ["==", "!=", "<=", ">=", "&&", "||"];
+
+const FUNCTIONS: [&str; 2] = ["empty", "len"];
+
+
+fn is_path_char(ch: char) -> bool {
+   matches!(ch, '.' | '@' | '?')
+}
+
+fn is_path_token(token: &TokenTree) -> bool {
+   match token {
+      TokenTree::Ident(_) | TokenTree::Literal(_) => true,
+      TokenTree::Punct(pt) => is_path_char(pt.as_char()),
+      TokenTree::Group(_) => false,
+   }
+}
+
+
+fn lex(input: TokenStream, env: &mut Env) -> Res<Vec<Lex>> {
+
+   let mut input = TokenIter::from(input);
+   let mut lexed = Vec::new();
+
+   while let Some(token) = input.next() { match token {
+
+      // quotes are evaluated in place
+      TokenTree::Punct(punct) if punct.as_char() == '$' => match parse_action(punct.span(), &mut input, env)? {
+         Action::Quote(quote) => {
+            let mut collector = TokenStream::new();
+            parse_quote(punct.span(), quote, &mut collector, env)?;
+            input.push_in_front(collector);
+         },
+         Action::Escape(escape) => err!(escape.span(), "unexpected token"),
+         Action::Assign(_, _, _) => err!(punct.span(), "unexpected assignment"),
+      },
+
+      // function calls
+      TokenTree::Ident(ident) if FUNCTIONS.contains(&ident.to_string().as_str()) => {
+         let mut span = ident.span();
+         let args = match_next!(span, input, Group(gp) if gp.delimiter() == Parenthesis);
+         lexed.push(Lex::Operand(Operand::Call(ident, args)));
+      },
+
+      // literals
+      TokenTree::Literal(lit) => lexed.push(Lex::Operand(Operand::Literal(lit))),
+
+      // paths
+      TokenTree::Ident(_) | TokenTree::Punct(_) if is_path_token(&token) => {
+
+         let mut span = token.span();
+         let mut path = vec![token];
+
+         while let Some(token) = input.next() {
+            // the default after `??` may be a group, indices after `.` negative, `@func` takes arguments
+            let after_default = matches!(&path[..], [.., TokenTree::Punct(a), TokenTree::Punct(b)] if a.as_char() == '?' && b.as_char() == '?');
+            let call = matches!(&token, TokenTree::Group(gp) if gp.delimiter() == Parenthesis)
+               && matches!(&path[..], [.., TokenTree::Punct(at), TokenTree::Ident(_)] if at.as_char() == '@');
+            let negative = matches!(&token, TokenTree::Punct(pt) if pt.as_char() == '-') && match path.last() {
+               Some(TokenTree::Punct(pt)) => pt.as_char() == '.',
+               Some(TokenTree::Literal(lit)) => lit.to_string().ends_with('.'), // lexed as float
+               _ => false,
+            };
+            if is_path_token(&token) || after_default || negative || call {
+               span = join(span, token.span());
+               path.push(token);
+            } else {
+               input.push_in_front(TokenStream::from(token));
+               break;
+            }
+         }
+
+         let mut group = Group::new(NoDelim, path.into_iter().collect());
+         group.set_span(span);
+
+         lexed.push(Lex::Operand(Operand::Path(group)));
+      },
+
+      // operators, joined only where that forms a known one
+      TokenTree::Punct(punct) => {
+
+         let mut span = punct.span();
+         let mut op = punct.as_char().to_string();
+
+         if punct.spacing() == Spacing::Joint {
+            match input.next() {
+               Some(TokenTree::Punct(next)) if JOINED_OPS.contains(&format!("{}{}", op, next.as_char()).as_str()) => {
+                  span = join(span, next.span());
+                  op.push(next.as_char());
+               },
+               Some(other) => input.push_in_front(TokenStream::from(other)),
+               None => {},
+            }
+         }
+
+         lexed.push(Lex::Op(op, span));
+      },
+
+      TokenTree::Group(gp) if gp.delimiter() == Parenthesis => lexed.push(Lex::Operand(Operand::Paren(gp))),
+      TokenTree::Group(gp) if gp.delimiter() == Brace => lexed.push(Lex::Operand(Operand::Stream(gp))),
+
+      other => err!(other.span(), "unexpected token"),
+   }}
+
+   Ok(lexed)
+}
+
+
+// precedence climbing
+
+type LexIter = std::iter::Peekable<std::vec::IntoIter<Lex>>;
+
+fn next_binary_op(input: &mut LexIter, min_prec: u8) -> Option<(u8, BinOp, Span)> {
+   let res = match input.peek() {
+      Some(Lex::Op(op, span)) => match binary_op(op) {
+         Some((prec, op)) if prec >= min_prec => Some((prec, op, *span)),
+         _ => None,
+      },
+      _ => None,
+   };
+   if res.is_some() { input.next(); }
+   res
+}
+
+fn parse_binary(span: Span, input: &mut LexIter, min_prec: u8) -> Res<Expr> {
+   let mut expr = parse_unary(span, input)?;
+   while let Some((prec, op, op_span)) = next_binary_op(input, min_prec) {
+      let rhs = parse_binary(op_span, input, prec + 1)?;
+      expr = Expr::Binary(op, op_span, expr.into(), rhs.into());
+   }
+   Ok(expr)
+}
+
+fn parse_unary(span: Span, input: &mut LexIter) -> Res<Expr> {
+   match input.next() {
+      Some(Lex::Op(op, span)) if op == "!" => Ok(Expr::Not(span, parse_unary(span, input)?.into())),
+      Some(Lex::Op(op, span)) if op == "-" => Ok(Expr::Neg(span, parse_unary(span, input)?.into())),
+      Some(Lex::Op(op, span)) => err!(span, "unexpected operator `{}`", op),
+      Some(Lex::Operand(operand)) => Ok(Expr::Operand(operand)),
+      None => err!(span, "unexpected end of input"),
+   }
+}
+
+
+fn parse_expr(group: &Group, env: &mut Env) -> Res<Expr> {
+
+   let mut input = lex(group.stream(), env)?.into_iter().peekable();
+   let expr = parse_binary(group.span(), &mut input, 0)?;
+
+   match input.next() {
+      None => Ok(expr),
+      Some(Lex::Op(op, span)) => err!(span, "unexpected operator `{}`", op),
+      Some(Lex::Operand(operand)) => err!(operand.span(), "expected an operator"),
+   }
+}
+
+
+// evaluation
+
+fn bool_item(value: bool, span: Span) -> Rc<Item> {
+   Item::Ident(Ident::new(if value { "true" } else { "false" }, span)).into()
+}
+
+fn as_int(item: &Item, span: Span) -> Res<(i128, String)> {
+   match item {
+      Item::Literal(lit) => match parse_int(lit) {
+         Some(int) => Ok(int),
+         None => err!(span, "expected an integer, found `{}`", lit),
+      },
+      _ => err!(span, "expected an integer, found a {} item", item.kind()),
+   }
+}
+
+fn int_item(value: Option<i128>, suffix: &str, span: Span, op: &str) -> Res<Rc<Item>> {
+   let (min, max) = int_bounds(suffix);
+   match value {
+      Some(value) if value >= min && value <= max => {
+         let mut lit = int_literal(value, suffix);
+         lit.set_span(span);
+         Ok(Item::Literal(lit).into())
+      },
+      _ => err!(span, "attempt to {} with overflow", op),
+   }
+}
+
+
+fn evaluate_operand(operand: Operand, env: &mut Env) -> Res<Rc<Item>> {
+   Ok(match operand {
+
+      Operand::Literal(lit) => Item::Literal(lit).into(),
+
+      Operand::Path(path) => {
+         let mut tokens = path.stream().into_iter();
+         match (tokens.next(), tokens.next()) {
+            (Some(TokenTree::Ident(id)), None) if id == "true" || id == "false" => Item::Ident(id).into(),
+            // bare identifiers are item names, compared identifiers are quoted
+            (Some(TokenTree::Ident(id)), None) if env.find_item(&id.to_string()).is_none() => {
+               parse_item_path(path, env).map_err(|err| err.help(format!("to compare with the identifier itself write `{{{}}}`", id)))?
+            },
+            _ => parse_item_path(path, env)?,
+         }
+      },
+
+      Operand::Call(func, args) => {
+         let span = join(func.span(), args.span());
+         match func.to_string().as_str() {
+            "empty" => bool_item(parse_list_path(args, env)?.is_empty(), span),
+            "len" => Item::Literal(Literal::usize_unsuffixed(parse_list_path(args, env)?.len())).into(),
+            _ => unreachable!(),
+         }
+      },
+
+      Operand::Paren(group) => evaluate_expr(parse_expr(&group, env)?, env)?,
+
+      Operand::Stream(group) => Item::Stream(evaluate_scoped_block(group.stream(), env)?).into(),
+   })
+}
+
+fn evaluate_expr(expr: Expr, env: &mut Env) -> Res<Rc<Item>> {
+   Ok(match expr {
+
+      Expr::Operand(operand) => evaluate_operand(operand, env)?,
+
+      Expr::Not(span, expr) => bool_item(!evaluate_expr(*expr, env)?.is_truthy(), span),
+
+      Expr::Neg(span, expr) => {
+         let (value, suffix) = as_int(evaluate_expr(*expr, env)?.as_ref(), span)?;
+         if suffix.starts_with('u') { err!(span, "can't negate an unsigned integer") }
+         int_item(value.checked_neg(), &suffix, span, "negate")?
+      },
+
+      // short circuiting
+      Expr::Binary(BinOp::And, span, lhs, rhs) => {
+         bool_item(evaluate_expr(*lhs, env)?.is_truthy() && evaluate_expr(*rhs, env)?.is_truthy(), span)
+      },
+      Expr::Binary(BinOp::Or, span, lhs, rhs) => {
+         bool_item(evaluate_expr(*lhs, env)?.is_truthy() || evaluate_expr(*rhs, env)?.is_truthy(), span)
+      },
+
+      // equality of non-integers compares items
+      Expr::Binary(op @ (BinOp::Eq | BinOp::Ne), span, lhs, rhs) => {
+
+         let lhs = evaluate_expr(*lhs, env)?;
+         let rhs = evaluate_expr(*rhs, env)?;
+
+         let equal = match (as_int(&lhs, span), as_int(&rhs, span)) {
+            (Ok((a, _)), Ok((b, _))) => a == b,
+            _ => lhs == rhs,
+         };
+
+         bool_item(equal == matches!(op, BinOp::Eq), span)
+      },
+
+      Expr::Binary(op, span, lhs, rhs) => {
+
+         let (a, a_type) = as_int(evaluate_expr(*lhs, env)?.as_ref(), span)?;
+         let (b, b_type) = as_int(evaluate_expr(*rhs, env)?.as_ref(), span)?;
+         let suffix = int_type(span, a_type, b_type)?;
+
+         match op {
+            BinOp::Lt => bool_item(a < b, span),
+            BinOp::Le => bool_item(a <= b, span),
+            BinOp::Gt => bool_item(a > b, span),
+            BinOp::Ge => bool_item(a >= b, span),
+            BinOp::Add => int_item(a.checked_add(b), &suffix, span, "add")?,
+            BinOp::Sub => int_item(a.checked_sub(b), &suffix, span, "subtract")?,
+            BinOp::Mul => int_item(a.checked_mul(b), &suffix, span, "multiply")?,
+            BinOp::Div | BinOp::Rem if b == 0 => err!(span, "attempt to divide by zero"),
+            BinOp::Div => int_item(a.checked_div(b), &suffix, span, "divide")?,
+            BinOp::Rem => int_item(a.checked_rem(b), &suffix, span, "calculate the remainder")?,
+            BinOp::And | BinOp::Or | BinOp::Eq | BinOp::Ne => unreachable!(),
+         }
+      },
+   })
+}
+
+
+pub fn evaluate_condition(cond: Group, env: &mut Env) -> Res<bool> {
+   Ok(evaluate_expr(parse_expr(&cond, env)?, env)?.is_truthy())
+}
+
+pub fn evaluate_expression(expr: Group, env: &mut Env) -> Res<Rc<Item>> {
+   evaluate_expr(parse_expr(&expr, env)?, env)
+}
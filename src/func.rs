@@ -0,0 +1,144 @@
+use proc_macro2::{TokenStream, TokenTree, Ident, Group, Span, Delimiter::None as NoDelim};
+use std::rc::Rc;
+
+use crate::*;
+
+
+// split evaluated function arguments at top level commas
+fn split_args(args: TokenStream) -> Vec<TokenStream> {
+
+   let mut list = vec![TokenStream::new()];
+
+   for token in args {
+      match token {
+         TokenTree::Punct(pt) if pt.as_char() == ',' => list.push(TokenStream::new()),
+         other => list.last_mut().unwrap().extend(Some(other)), // never empty
+      }
+   }
+
+   if list.last().unwrap().is_empty() { list.pop(); } // trailing comma
+
+   list
+}
+
+
+// optionally negative integer
+fn parse_bound(mut span: Span, input: &mut TokenIter) -> Res<(i128, String, Span)> {
+
+   let negative = match next!(span, input) {
+      TokenTree::Punct(pt) if pt.as_char() == '-' => true,
+      other => { input.push_in_front(TokenStream::from(other)); false },
+   };
+
+   let lit = match_next!(span, input, Literal);
+
+   match parse_int(&lit) {
+      Some((value, suffix)) => Ok((if negative { -value } else { value }, suffix, span)),
+      None => err!(span, "expected an integer, found `{}`", lit),
+   }
+}
+
+fn next_punct(mut span: Span, input: &mut TokenIter, ch: char) -> Res<Span> {
+   match_next!(span, input, Punct(pt) if pt.as_char() == ch);
+   Ok(span)
+}
+
+
+// `start..end`, `start..=end`, optionally followed by `, step`
+fn parse_range(span: Span, args: TokenStream) -> Res<Range> {
+
+   let mut args = split_args(args).into_iter();
+
+   let mut input = TokenIter::from(args.next().unwrap_or_default());
+
+   let (start, start_suffix, start_span) = parse_bound(span, &mut input)?;
+
+   let span = next_punct(span, &mut input, '.')?;
+   let span = next_punct(span, &mut input, '.')?;
+
+   let inclusive = match input.next() {
+      Some(TokenTree::Punct(pt)) if pt.as_char() == '=' => true,
+      Some(other) => { input.push_in_front(TokenStream::from(other)); false },
+      None => false,
+   };
+
+   let (end, end_suffix, end_span) = parse_bound(span, &mut input)?;
+
+   if let Some(token) = input.next() { err!(token.span(), "unexpected token") }
+
+   let suffix = int_type(span, start_suffix, end_suffix)?;
+
+   let step = match args.next() {
+      Some(step) => {
+         let mut input = TokenIter::from(step);
+         let (step, _, _) = parse_bound(span, &mut input)?;
+         if let Some(token) = input.next() { err!(token.span(), "unexpected token") }
+         if step == 0 { err!(span, "range step can't be zero") }
+         step
+      },
+      None => 1,
+   };
+
+   if let Some(arg) = args.next() {
+      err!(arg.into_iter().next().map_or(span, |tk| tk.span()), "unexpected argument")
+   }
+
+   let (min, max) = int_bounds(&suffix);
+
+   for (bound, bound_span) in [(start, start_span), (end, end_span)] {
+      if bound < 0 && min == 0 { err!(bound_span, "negative bound `{}` in a `{}` range", bound, suffix) }
+   }
+
+   let end = if inclusive { end + step.signum() } else { end };
+   let len = (((end - start) + step - step.signum()) / step).max(0);
+
+   // the elements have to fit the type, the last one may not be the end bound
+   if len > 0 {
+      let last = start + step * (len - 1);
+      if start < min || start > max { err!(start_span, "range start `{}` is out of range for `{}`", start, suffix) }
+      if last < min || last > max { err!(end_span, "range element `{}` is out of range for `{}`", last, suffix) }
+   }
+
+   Ok(Range { start, step, len: len as usize, suffix })
+}
+
+
+const FUNCTIONS: [&str; 31] = [
+   "range", "include_json", "has", "parse_item", "zip", "chain", "product", "reverse", "enumerate", "skip", "take", "step_by", "windows", "chunks",
+   "sort", "sort_by_key", "unique", "flatten", "keys", "values", "entries", "concat", "merge", "group_by",
+   "split_for_impl", "impl_generics", "ty_generics", "where_clause", "add_type_param", "add_lifetime", "add_bound",
+];
+
+
+// `@name(args)` item functions, `@name{..}` for code arguments
+pub fn call_function(func: Ident, args: Group, env: &mut Env) -> Res<Rc<Item>> {
+
+   let span = join(func.span(), args.span());
+   let name = func.to_string();
+
+   let args = evaluate_scoped_block(args.stream(), env)?;
+
+   Ok(match name.as_str() {
+      "range" => Item::Range(parse_range(span, args)?).into(),
+      "include_json" => include_json(span, args, env)?,
+      "parse_item" => parse_item(span, args)?,
+      "has" => {
+         let mut path = Group::new(NoDelim, args);
+         path.set_span(span);
+         let found = find_item_path(path, true, env)?.is_some();
+         Item::Ident(Ident::new(if found { "true" } else { "false" }, span)).into()
+      },
+      _ => {
+         let args = split_args(args);
+         if let Some(item) = call_combinator(span, &name, args.clone(), env)? { item }
+         else if let Some(item) = call_transform(span, &name, args.clone(), env)? { item }
+         else if let Some(item) = call_generics(span, &name, args, env)? { item }
+         else {
+            return Err(
+               Error::new(func.span(), format!("unknown function `@{}`", name))
+               .help(format!("expected one of {}", FUNCTIONS.map(|name| format!("`@{}`", name)).join(", ")))
+            )
+         }
+      },
+   })
+}
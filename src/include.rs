@@ -0,0 +1,85 @@
+use proc_macro2::{TokenStream, TokenTree, Ident, Literal, Span};
+use syn::{parse_str, LitStr};
+use serde_json::Value;
+use indexmap::IndexMap;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+use crate::*;
+
+
+// bools become identifiers, strings and numbers literals, `$#{..}` turns strings into identifiers
+fn json_item(span: Span, value: Value) -> Res<Rc<Item>> {
+   Ok(match value {
+
+      Value::Null => err!(span, "null values are not supported"),
+
+      Value::Bool(value) => Item::Ident(Ident::new(if value { "true" } else { "false" }, span)).into(),
+
+      Value::Number(number) => {
+         let mut lit = if let Some(int) = number.as_u64() { Literal::u64_unsuffixed(int) }
+            else if let Some(int) = number.as_i64() { Literal::i64_unsuffixed(int) }
+            else { Literal::f64_unsuffixed(number.as_f64().unwrap()) }; // always representable
+         lit.set_span(span);
+         Item::Literal(lit).into()
+      },
+
+      Value::String(string) => {
+         let mut lit = Literal::string(&string);
+         lit.set_span(span);
+         Item::Literal(lit).into()
+      },
+
+      Value::Array(values) => {
+         Item::List(values.into_iter().map(|value| json_item(span, value)).collect::<Res<_>>()?).into()
+      },
+
+      Value::Object(entries) => {
+         let mut map = IndexMap::new();
+         for (key, value) in entries { map.insert(key, json_item(span, value)?); }
+         Item::Map(map).into()
+      },
+   })
+}
+
+
+// path string literal relative to the manifest dir
+fn file_path(span: Span, args: TokenStream) -> Res<(Span, PathBuf)> {
+
+   let mut args = args.into_iter();
+
+   let lit = match (args.next(), args.next()) {
+      (Some(TokenTree::Literal(lit)), None) => lit,
+      _ => err!(span, "expected a file path string"),
+   };
+
+   let path = match parse_str::<LitStr>(&lit.to_string()) {
+      Ok(path) => path.value(),
+      Err(_) => err!(lit.span(), "expected a file path string"),
+   };
+
+   let root = std::env::var("CARGO_MANIFEST_DIR").unwrap_or_default();
+
+   Ok((lit.span(), PathBuf::from(root).join(path)))
+}
+
+
+// `@include_json("file.json")`
+pub fn include_json(span: Span, args: TokenStream, env: &mut Env) -> Res<Rc<Item>> {
+
+   let (path_span, path) = file_path(span, args)?;
+
+   let text = match std::fs::read_to_string(&path) {
+      Ok(text) => text,
+      Err(err) => err!(path_span, "couldn't read `{}`: {}", path.display(), err),
+   };
+
+   let value = match serde_json::from_str(&text) {
+      Ok(value) => value,
+      Err(err) => err!(path_span, "couldn't parse `{}`: {}", path.display(), err),
+   };
+
+   env.track_file(path.to_string_lossy().into_owned());
+
+   json_item(path_span, value)
+}
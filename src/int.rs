@@ -0,0 +1,71 @@
+use proc_macro2::{Literal, Span};
+use std::str::FromStr;
+
+use crate::Res;
+
+
+const SUFFIXES: [&str; 12] = [
+   "i128", "isize", "i64", "i32", "i16", "i8",
+   "u128", "usize", "u64", "u32", "u16", "u8",
+];
+
+
+// split an integer literal into its value and type suffix
+pub fn parse_int(lit: &Literal) -> Option<(i128, String)> {
+
+   let string = lit.to_string().replace('_', "");
+
+   let (negative, string) = match string.strip_prefix('-') {
+      Some(rest) => (true, rest),
+      None => (false, string.as_str()),
+   };
+
+   let (radix, digits) = match string.get(..2) {
+      Some("0x") => (16, &string[2..]),
+      Some("0o") => (8, &string[2..]),
+      Some("0b") => (2, &string[2..]),
+      _ => (10, string),
+   };
+
+   let suffix = SUFFIXES.iter().find(|sfx| digits.ends_with(*sfx));
+   let digits = &digits[..digits.len() - suffix.map_or(0, |sfx| sfx.len())];
+
+   let value = i128::from_str_radix(digits, radix).ok()?;
+
+   Some((if negative { -value } else { value }, suffix.map_or(String::new(), |sfx| sfx.to_string())))
+}
+
+
+pub fn int_literal(value: i128, suffix: &str) -> Literal {
+   if suffix.is_empty() {
+      Literal::i128_unsuffixed(value)
+   } else {
+      Literal::from_str(&format!("{}{}", value, suffix)).unwrap() // valid by construction
+   }
+}
+
+
+// common type of two suffixes, unsuffixed adapts
+pub fn int_type(span: Span, a: String, b: String) -> Res<String> {
+   Ok(match (a.as_str(), b.as_str()) {
+      (_, "") => a,
+      ("", _) => b,
+      (x, y) if x == y => a,
+      (x, y) => err!(span, "mismatched integer types `{}` and `{}`", x, y),
+   })
+}
+
+pub fn int_bounds(suffix: &str) -> (i128, i128) {
+   match suffix {
+      "i8" => (i8::MIN.into(), i8::MAX.into()),
+      "i16" => (i16::MIN.into(), i16::MAX.into()),
+      "i32" => (i32::MIN.into(), i32::MAX.into()),
+      "i64" | "isize" => (i64::MIN.into(), i64::MAX.into()),
+      "u8" => (0, u8::MAX.into()),
+      "u16" => (0, u16::MAX.into()),
+      "u32" => (0, u32::MAX.into()),
+      "u64" | "usize" => (0, u64::MAX.into()),
+      "u128" => (0, i128::MAX),
+      _ => (i128::MIN, i128::MAX),
+   }
+}
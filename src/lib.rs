@@ -1,34 +1,117 @@
 
 // mods
 
-type Res<T> = Result<T, (proc_macro2::Span, &'static str)>;
+type Res<T> = Result<T, Error>;
+
+// joined span if the compiler supports it, the first one otherwise
+fn join(span: proc_macro2::Span, other: proc_macro2::Span) -> proc_macro2::Span {
+   span.join(other).unwrap_or(span)
+}
 
 #[macro_use]
 mod macros;
+mod error;        use error::*;
 mod token_iter;   use token_iter::*;
+mod int;          use int::*;
+mod case;         use case::*;
+mod stringify;    use stringify::*;
 mod env;          use env::*;
+mod pattern;      use pattern::*;
 mod quotes;       use quotes::*;
 mod assign;       use assign::*;
+mod expr;         use expr::*;
+mod list;         use list::*;
+mod func;         use func::*;
+mod include;      use include::*;
+mod syntax;       use syntax::*;
 mod action;       use action::*;
 mod parse;        use parse::*;
+mod library;      use library::*;
 
 
 // exports
 
 use proc_macro::{TokenStream as TokenStream1};
 use proc_macro2::{TokenStream, TokenTree, Literal};
-use quote::{quote, quote_spanned};
+use quote::quote;
+
+
+/// Evaluates the template and emits its output.
+///
+/// `$def name(params) {..}` bodies see the bindings where they are defined and the arguments
+/// of a `$name!(..)` call, which has to pass one argument per parameter:
+///
+/// ```compile_fail
+/// template_macro::template!{
+///    $def pair(a, b) { $(a) + $(b) }
+///    pub const A: u8 = $pair!(1);
+/// }
+/// ```
+///
+/// Calls nest up to a recursion limit:
+///
+/// ```compile_fail
+/// template_macro::template!{
+///    $def endless(n) { $endless!(n) }
+///    pub const A: u8 = 0 $endless!(1);
+/// }
+/// ```
+#[proc_macro]
+pub fn template(input: TokenStream1) -> TokenStream1 {
+
+   match evaluate(TokenStream::new(), input.into()) {
+      Ok(output) => output.into(),
+      Err(err) => err.to_compile_errors().into(),
+   }
+}
 
 
 #[proc_macro]
-pub fn template(input: TokenStream1) -> TokenStream1 {
+pub fn template_lib(input: TokenStream1) -> TokenStream1 {
+
+   match define_library(input.into()) {
+      Ok(output) => output.into(),
+      Err(err) => err.to_compile_errors().into(),
+   }
+}
+
+
+/// Evaluates the template in the arguments with the annotated item bound as `item`, a map like
+/// `@parse_item` gives, and as `item_tokens`. Named apart from `template` since both are exported
+/// from this crate.
+///
+/// The item itself is replaced by the output, so the template has to quote `$(item_tokens)` to keep it:
+///
+/// ```
+/// #[template_macro::template_attr(
+///    $(item_tokens)
+///    pub const NAME: &str = $str{$(item.name)};
+/// )]
+/// pub struct Config;
+///
+/// assert_eq!(NAME, "Config");
+/// # let _ = Config;
+/// ```
+#[proc_macro_attribute]
+pub fn template_attr(args: TokenStream1, item: TokenStream1) -> TokenStream1 {
+
+   let item: TokenStream = item.into();
+
+   match evaluate_attr(args.into(), item.clone()) {
+      Ok(output) => output.into(),
+      // keep the item to avoid follow-up errors
+      Err(err) => item.into_iter().chain(err.to_compile_errors()).collect::<TokenStream>().into(),
+   }
+}
+
+
+#[doc(hidden)]
+#[proc_macro]
+pub fn template_with_lib(input: TokenStream1) -> TokenStream1 {
 
-   match evaluate(input.into()) {
+   match split_library(input.into()).and_then(|(lib, input)| evaluate(lib, input)) {
       Ok(output) => output.into(),
-      Err((span, err)) => {
-         let string_lit = Literal::string(&err);
-         quote_spanned!{span=>compile_error!(#string_lit)}.into()
-      }
+      Err(err) => err.to_compile_errors().into(),
    }
 }
 
@@ -36,16 +119,13 @@ pub fn template(input: TokenStream1) -> TokenStream1 {
 #[proc_macro]
 pub fn debug_template(input: TokenStream1) -> TokenStream1 {
 
-   match evaluate(input.into()) {
+   match evaluate(TokenStream::new(), input.into()) {
       Ok(output) => {
          let debug = output.to_string();
          let string_lit = Literal::string(&debug);
          quote!{compile_error!(#string_lit)}.into()
       },
-      Err((span, err)) => {
-         let string_lit = Literal::string(&err);
-         quote_spanned!{span=>compile_error!(#string_lit)}.into()
-      }
+      Err(err) => err.to_compile_errors().into(),
    }
 }
 
@@ -0,0 +1,85 @@
+use proc_macro2::{TokenStream, TokenTree, Group, Ident, Punct, Span, Spacing, Delimiter::{Brace, Bracket}};
+use quote::quote;
+
+use crate::*;
+
+
+// a bare `$` can start a repetition in `macro_rules!`, an unbound `$marker` is transcribed as is
+const MARKER: &str = "__template_lib";
+
+fn escape_dollars(input: TokenStream) -> TokenStream {
+   input.into_iter().flat_map(|token| -> Vec<TokenTree> { match token {
+      TokenTree::Punct(pt) if pt.as_char() == '$' => {
+         let mut punct = Punct::new('$', Spacing::Alone);
+         punct.set_span(pt.span());
+         vec![punct.into(), Ident::new(MARKER, pt.span()).into()]
+      },
+      TokenTree::Group(gp) => {
+         let mut group = Group::new(gp.delimiter(), escape_dollars(gp.stream()));
+         group.set_span(gp.span());
+         vec![group.into()]
+      },
+      other => vec![other],
+   }}).collect()
+}
+
+fn unescape_dollars(input: TokenStream) -> TokenStream {
+   input.into_iter().filter_map(|token| match token {
+      TokenTree::Ident(ident) if ident == MARKER => None,
+      TokenTree::Group(gp) => {
+         let mut group = Group::new(gp.delimiter(), unescape_dollars(gp.stream()));
+         group.set_span(gp.span());
+         Some(group.into())
+      },
+      other => Some(other),
+   }).collect()
+}
+
+
+// `#[attrs] name { template }` to a `macro_rules!` shim forwarding to `template_with_lib!`
+pub fn define_library(input: TokenStream) -> Res<TokenStream> {
+
+   let mut input = TokenIter::from(input);
+   let mut span = Span::call_site();
+   let mut attrs = Vec::new();
+
+   let name = loop {
+      match next!(span, input) {
+         TokenTree::Punct(pt) if pt.as_char() == '#' => {
+            let attr = match_next!(span, input, Group(gp) if gp.delimiter() == Bracket);
+            attrs.push(quote!{ #pt #attr });
+         },
+         TokenTree::Ident(ident) => break ident,
+         _ => err!(span, "unexpected token"),
+      }
+   };
+
+   let payload = match_next!(span, input, Group(blk) if blk.delimiter() == Brace);
+
+   if let Some(token) = input.next() { err!(token.span(), "unexpected token") }
+
+   // report errors at the definition
+   let mut env = Env::new();
+   env.push_scope(None);
+   parse_block(payload.stream(), &mut TokenStream::new(), &mut env)?;
+
+   let payload = escape_dollars(payload.stream());
+
+   Ok(quote!{
+      #(#attrs)*
+      macro_rules! #name {
+         ($($body:tt)*) => {
+            ::template_macro::template_with_lib!{ { #payload } $($body)* }
+         };
+      }
+   })
+}
+
+
+// `{ lib } template`
+pub fn split_library(input: TokenStream) -> Res<(TokenStream, TokenStream)> {
+   let mut input = TokenIter::from(input);
+   let mut span = Span::call_site();
+   let lib = match_next!(span, input, Group(blk) if blk.delimiter() == Brace);
+   Ok((unescape_dollars(lib.stream()), input.collect()))
+}
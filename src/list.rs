@@ -0,0 +1,293 @@
+use proc_macro2::{TokenStream, TokenTree, Group, Literal, Span, Delimiter::None as NoDelim};
+use syn::{parse_str, LitStr};
+use indexmap::IndexMap;
+use std::cmp::Ordering;
+use std::rc::Rc;
+
+use crate::*;
+
+
+// function arguments
+
+fn arg_span(span: Span, arg: &TokenStream) -> Span {
+   let mut tokens = arg.clone().into_iter();
+   match tokens.next() {
+      Some(first) => tokens.fold(first.span(), |span, token| join(span, token.span())),
+      None => span,
+   }
+}
+
+// item path or a `(...)` list or `{...}` map literal
+pub fn arg_item(span: Span, arg: TokenStream, env: &mut Env) -> Res<(Span, Rc<Item>)> {
+
+   let span = arg_span(span, &arg);
+
+   let mut tokens = arg.clone().into_iter();
+
+   if let (Some(TokenTree::Group(gp)), None) = (tokens.next(), tokens.next()) && gp.delimiter() != NoDelim {
+      let assign = parse_assign_value(span, &mut arg.into(), env)?;
+      return Ok((span, parse_assign(assign, env)?));
+   }
+
+   let mut path = Group::new(NoDelim, arg);
+   path.set_span(span);
+   Ok((span, parse_list_path(path, env)?))
+}
+
+// integer literal or an item holding one
+pub fn arg_count(span: Span, arg: TokenStream, env: &mut Env) -> Res<usize> {
+
+   let mut tokens = arg.clone().into_iter();
+
+   let lit = match (tokens.next(), tokens.next()) {
+      (Some(TokenTree::Literal(lit)), None) => lit,
+      _ => {
+         let (span, item) = arg_item(span, arg, env)?;
+         match item.as_ref() {
+            Item::Literal(lit) => lit.clone(),
+            other => err!(span, "expected an integer, found a {} item", other.kind()),
+         }
+      },
+   };
+
+   match parse_int(&lit) {
+      Some((value, _)) if value >= 0 && value <= usize::MAX as i128 => Ok(value as usize),
+      _ => err!(lit.span(), "expected a non-negative integer, found `{}`", lit),
+   }
+}
+
+pub fn expect_args(span: Span, name: &str, args: &[TokenStream], min: usize, max: usize) -> Res<()> {
+   if args.len() < min || args.len() > max {
+      let expected = if min == max { min.to_string() } else if max == usize::MAX { format!("at least {}", min) } else { format!("{} to {}", min, max) };
+      err!(
+         span, "`@{}` takes {} argument{} but {} {} supplied",
+         name, expected, if min == 1 { "" } else { "s" }, args.len(), if args.len() == 1 { "was" } else { "were" },
+      )
+   }
+   Ok(())
+}
+
+
+// elements of an iterable item, scalars are single elements
+pub fn list_items(span: Span, item: &Rc<Item>) -> Res<Vec<Rc<Item>>> {
+   Ok(match item.as_ref() {
+      Item::List(list) => list.clone(),
+      Item::Range(range) => range.iter().map(|lit| Item::Literal(lit).into()).collect(),
+      Item::Ident(_) | Item::Literal(_) | Item::Stream(_) => vec![Rc::clone(item)],
+      Item::Map(_) | Item::Def(_) => err!(span, "expected a list, found a {} item", item.kind()),
+   })
+}
+
+fn list(items: impl IntoIterator<Item=Rc<Item>>) -> Rc<Item> {
+   Item::List(items.into_iter().collect()).into()
+}
+
+
+// iteration combinators
+pub fn call_combinator(span: Span, name: &str, args: Vec<TokenStream>, env: &mut Env) -> Res<Option<Rc<Item>>> {
+
+   let variadic = matches!(name, "zip" | "chain" | "product");
+   let counted = matches!(name, "skip" | "take" | "step_by" | "windows" | "chunks");
+
+   if variadic { expect_args(span, name, &args, 1, usize::MAX)? }
+   else if counted { expect_args(span, name, &args, 2, 2)? }
+   else if matches!(name, "reverse" | "enumerate") { expect_args(span, name, &args, 1, 1)? }
+   else { return Ok(None) }
+
+   let mut args = args.into_iter();
+
+   let mut lists = Vec::new();
+
+   if variadic {
+      for arg in args.by_ref() {
+         let (span, item) = arg_item(span, arg, env)?;
+         lists.push(list_items(span, &item)?);
+      }
+   } else {
+      let (span, item) = arg_item(span, args.next().unwrap(), env)?; // checked
+      lists.push(list_items(span, &item)?);
+   }
+
+   let count = match args.next() {
+      Some(arg) => arg_count(span, arg, env)?,
+      None => 0,
+   };
+
+   if count == 0 && matches!(name, "step_by" | "windows" | "chunks") {
+      err!(span, "`@{}` needs a size greater than zero", name)
+   }
+
+   let items = lists.remove(0);
+
+   Ok(Some(match name {
+
+      "zip" => {
+         let len = lists.iter().map(Vec::len).fold(items.len(), usize::min);
+         list((0..len).map(|i| list(Some(Rc::clone(&items[i])).into_iter().chain(lists.iter().map(|other| Rc::clone(&other[i]))))))
+      },
+
+      "chain" => list(items.into_iter().chain(lists.into_iter().flatten())),
+
+      "product" => {
+         let mut product: Vec<Vec<Rc<Item>>> = items.into_iter().map(|item| vec![item]).collect();
+         for other in lists {
+            product = product.into_iter().flat_map(|prefix| other.iter().map(move |item| {
+               let mut tuple = prefix.clone();
+               tuple.push(Rc::clone(item));
+               tuple
+            })).collect();
+         }
+         list(product.into_iter().map(list))
+      },
+
+      "reverse" => list(items.into_iter().rev()),
+
+      "enumerate" => list(items.into_iter().enumerate().map(|(i, item)| {
+         let mut index = Literal::usize_unsuffixed(i);
+         index.set_span(span);
+         list([Item::Literal(index).into(), item])
+      })),
+
+      "skip" => list(items.into_iter().skip(count)),
+      "take" => list(items.into_iter().take(count)),
+      "step_by" => list(items.into_iter().step_by(count)),
+
+      "windows" => list(items.windows(count).map(|window| list(window.iter().cloned()))),
+      "chunks" => list(items.chunks(count).map(|chunk| list(chunk.iter().cloned()))),
+
+      _ => unreachable!(),
+   }))
+}
+
+
+fn map_entries(span: Span, item: &Rc<Item>) -> Res<IndexMap<String, Rc<Item>>> {
+   match item.as_ref() {
+      Item::Map(map) => Ok(map.clone()),
+      other => err!(span, "expected a map, found a {} item", other.kind()),
+   }
+}
+
+// map keys from items, string literals give their value
+fn key_text(span: Span, item: &Item) -> Res<String> {
+   match item {
+      Item::Ident(ident) => Ok(ident.to_string()),
+      Item::Literal(lit) => Ok(match parse_str::<LitStr>(&lit.to_string()) {
+         Ok(string) => string.value(),
+         Err(_) => lit.to_string(),
+      }),
+      other => err!(span, "expected an identifier or literal key, found a {} item", other.kind()),
+   }
+}
+
+// integers compare by value, anything else by its tokens
+pub fn compare(a: &Item, b: &Item) -> Ordering {
+   match (a, b) {
+      (Item::Literal(a), Item::Literal(b)) => match (parse_int(a), parse_int(b)) {
+         (Some((a, _)), Some((b, _))) => a.cmp(&b),
+         _ => a.to_string().cmp(&b.to_string()),
+      },
+      (a, b) => a.to_token_string().cmp(&b.to_token_string()),
+   }
+}
+
+// key expression evaluated with the element as `@value`
+fn element_key(span: Span, key: &TokenStream, index: usize, element: &Rc<Item>, env: &mut Env) -> Res<Rc<Item>> {
+   let iter_scope = IterScope { first: false, last: false, index, key: index.to_string(), value: Rc::clone(element) };
+   Ok(with_iter_scope(env, iter_scope, Vec::new(), |env| arg_item(span, key.clone(), env))?.1)
+}
+
+
+// list and map transformations
+pub fn call_transform(span: Span, name: &str, args: Vec<TokenStream>, env: &mut Env) -> Res<Option<Rc<Item>>> {
+
+   match name {
+      "sort" | "unique" | "flatten" | "keys" | "values" | "entries" => expect_args(span, name, &args, 1, 1)?,
+      "sort_by_key" | "group_by" => expect_args(span, name, &args, 2, 2)?,
+      "concat" | "merge" => expect_args(span, name, &args, 1, usize::MAX)?,
+      _ => return Ok(None),
+   }
+
+   let mut args = args.into_iter();
+   let (item_span, item) = arg_item(span, args.next().unwrap(), env)?; // checked
+
+   Ok(Some(match name {
+
+      "sort" => match item.as_ref() {
+         Item::Map(map) => {
+            let mut map = map.clone();
+            map.sort_keys();
+            Item::Map(map).into()
+         },
+         _ => {
+            let mut items = list_items(item_span, &item)?;
+            items.sort_by(|a, b| compare(a, b));
+            list(items)
+         },
+      },
+
+      "sort_by_key" => {
+         let key = args.next().unwrap(); // checked
+         let mut keyed = Vec::new();
+         for (i, element) in list_items(item_span, &item)?.into_iter().enumerate() {
+            keyed.push((element_key(span, &key, i, &element, env)?, element));
+         }
+         keyed.sort_by(|(a, _), (b, _)| compare(a, b));
+         list(keyed.into_iter().map(|(_, element)| element))
+      },
+
+      "unique" => {
+         let mut items: Vec<Rc<Item>> = Vec::new();
+         for element in list_items(item_span, &item)? {
+            if !items.contains(&element) { items.push(element) }
+         }
+         list(items)
+      },
+
+      "flatten" => {
+         let mut items = Vec::new();
+         for element in list_items(item_span, &item)? {
+            match element.as_ref() {
+               Item::List(_) | Item::Range(_) => items.extend(list_items(item_span, &element)?),
+               _ => items.push(element),
+            }
+         }
+         list(items)
+      },
+
+      "keys" => list(map_entries(item_span, &item)?.keys().map(|key| key_item(key, item_span))),
+
+      "values" => list(map_entries(item_span, &item)?.into_values()),
+
+      "entries" => list(map_entries(item_span, &item)?.into_iter().map(|(key, value)| list([key_item(&key, item_span), value]))),
+
+      "concat" => {
+         let mut items = list_items(item_span, &item)?;
+         for arg in args {
+            let (span, item) = arg_item(span, arg, env)?;
+            items.extend(list_items(span, &item)?);
+         }
+         list(items)
+      },
+
+      "merge" => {
+         let mut map = map_entries(item_span, &item)?;
+         for arg in args {
+            let (span, item) = arg_item(span, arg, env)?;
+            map.extend(map_entries(span, &item)?);
+         }
+         Item::Map(map).into()
+      },
+
+      "group_by" => {
+         let key = args.next().unwrap(); // checked
+         let mut groups: IndexMap<String, Vec<Rc<Item>>> = IndexMap::new();
+         for (i, element) in list_items(item_span, &item)?.into_iter().enumerate() {
+            let key = key_text(span, element_key(span, &key, i, &element, env)?.as_ref())?;
+            groups.entry(key).or_default().push(element);
+         }
+         Item::Map(groups.into_iter().map(|(key, items)| (key, list(items))).collect()).into()
+      },
+
+      _ => unreachable!(),
+   }))
+}
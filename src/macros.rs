@@ -1,8 +1,8 @@
 
-// return an error
+// return an error, the message takes format arguments
 macro_rules! err {
-   ($span:expr, $err:expr) => {
-      { return Err(($span, $err)) }
+   ($span:expr, $($msg:tt)+) => {
+      { return Err($crate::Error::new($span, format!($($msg)+))) }
    };
 }
 
@@ -1,6 +1,9 @@
 
-use proc_macro2::{TokenStream, TokenTree, Group, Ident, Literal};
+use proc_macro2::{TokenStream, TokenTree, Group, Ident, Punct, Literal, Span, Spacing, Delimiter::{Parenthesis, Brace}, token_stream::IntoIter};
+use syn::{parse_str, parse2, parse_quote, parse::Parser, File, Item as SynItem, Expr, Block};
+use quote::quote;
 
+use std::iter::Peekable;
 use std::rc::Rc;
 use std::str::FromStr;
 
@@ -8,38 +11,136 @@ use crate::*;
 
 
 
-pub fn evaluate(input: TokenStream) -> Res<TokenStream> {
+pub fn evaluate(lib: TokenStream, input: TokenStream) -> Res<TokenStream> {
 
-   let mut output = TokenStream::new();
    let mut scope = Env::new();
    scope.push_scope(None);
 
-   match parse_block(input, &mut output, &mut scope) {
-      Ok(()) => Ok(output),
-      Err(err) => Err(err),
+   // library items are seeded into the root scope, its output is discarded
+   parse_block(lib, &mut TokenStream::new(), &mut scope)?;
+
+   evaluate_in(scope, input, TokenStream::new())
+}
+
+
+// the annotated item is emitted unchanged and bound as `item_tokens`, supported items also as `item`
+pub fn evaluate_attr(args: TokenStream, item: TokenStream) -> Res<TokenStream> {
+
+   let mut scope = Env::new();
+   scope.push_scope(None);
+
+   scope.set_item("item_tokens".to_string(), Item::Stream(item.clone()).into());
+
+   if let Ok(parsed) = parse_item(Span::call_site(), item) {
+      scope.set_item("item".to_string(), parsed);
    }
+
+   evaluate_in(scope, args, TokenStream::new())
 }
 
 
+// extra items have to fit wherever the template expands: next to items, into a fn body or
+// const value when the output could be impl items, around an expression or before statements
+fn attach_items(output: TokenStream, items: TokenStream) -> TokenStream {
+
+   if items.is_empty() || output.is_empty() { return output }
+
+   let extra = quote!{ const _: () = { #items }; };
+
+   if let Ok(mut file) = parse2::<File>(output.clone()) {
+
+      let impl_items = file.items.iter().all(|item| matches!(
+         item, SynItem::Fn(_) | SynItem::Const(_) | SynItem::Type(_) | SynItem::Macro(_) | SynItem::Verbatim(_)
+      ));
+
+      if impl_items {
+         for item in &mut file.items {
+            match item {
+               SynItem::Fn(func) => {
+                  func.block.stmts.insert(0, parse_quote!{ #extra });
+                  return quote!{ #file }
+               },
+               SynItem::Const(cons) => {
+                  let expr = &cons.expr;
+                  *cons.expr = parse_quote!{{ #extra #expr }};
+                  return quote!{ #file }
+               },
+               _ => {},
+            }
+         }
+         // nothing to host them, e.g. in a trait
+         output
+      }
+      else { quote!{ #output #extra } }
+   }
+   // a path could be a type as well
+   else if let Ok(expr) = parse2::<Expr>(output.clone()) {
+      if let Expr::Path(_) = expr { output } else { quote!{{ #extra #output }} }
+   }
+   else if Block::parse_within.parse2(output.clone()).is_ok() {
+      quote!{ #extra #output }
+   }
+   else { output }
+}
+
+fn evaluate_in(mut scope: Env, input: TokenStream, mut output: TokenStream) -> Res<TokenStream> {
+
+   parse_block(input, &mut output, &mut scope)?;
+
+   let mut items = TokenStream::new();
+
+   // rebuild when included files change
+   for path in scope.tracked_files() {
+      let path = Literal::string(path);
+      items.extend(quote!{ const _: &[u8] = include_bytes!(#path); });
+   }
+
+   // lint-like warnings through a deprecated constant
+   for (span, message) in scope.warnings() {
+      let name = Ident::new("template_warning", *span);
+      items.extend(quote!{
+         const _: () = {
+            #[deprecated(note = #message)]
+            #[allow(non_upper_case_globals)]
+            const template_warning: () = ();
+            #name
+         };
+      });
+   }
+
+   Ok(attach_items(output, items))
+}
+
+
+// quote errors are gathered, assignment and syntax errors stop the block
 pub fn parse_block(input: TokenStream, output: &mut TokenStream, env: &mut Env) -> Res<()> {
 
    let mut input = TokenIter::from(input);
+   let mut errors = Error::empty();
 
    while let Some(token) = input.next() { match token {
 
       TokenTree::Punct(punct) => match punct.as_char() {
 
          // action signifier
-         '$' => match parse_action(punct.span(), &mut input, env)? {
+         '$' => match parse_action(punct.span(), &mut input, env) {
 
-            Action::Escape(escaped) => output.extend(Some(TokenTree::from(escaped))),
+            Ok(Action::Escape(escaped)) => output.extend(Some(TokenTree::from(escaped))),
+
+            Ok(Action::Assign(kind, ident, assign)) => {
+               if let Err(err) = parse_assign(assign, env).and_then(|item| assign_item(kind, ident, item, env)) {
+                  errors.combine(err);
+                  break;
+               }
+            },
 
-            Action::Assign(id, assign) => {
-               let item = parse_assign(assign, env)?;
-               env.set_item(id, item);
+            Ok(Action::Quote(quote)) => {
+               if let Err(err) = parse_quote(punct.span(), quote, output, env) {
+                  errors.combine(err);
+               }
             },
 
-            Action::Quote(quote) => parse_quote(punct.span(), quote, output, env)?,
+            Err(err) => { errors.combine(err); break },
          },
 
          // any other
@@ -50,7 +151,10 @@ pub fn parse_block(input: TokenStream, output: &mut TokenStream, env: &mut Env)
 
          // parse recursively
          let mut collector = TokenStream::new();
-         parse_block(group.stream(), &mut collector, env)?;
+
+         if let Err(err) = parse_block(group.stream(), &mut collector, env) {
+            errors.combine(err);
+         }
 
          let mut collect_group = Group::new(group.delimiter(), collector);
          collect_group.set_span(group.span());
@@ -61,17 +165,132 @@ pub fn parse_block(input: TokenStream, output: &mut TokenStream, env: &mut Env)
       other => output.extend(Some(other)),
    }}
 
-   Ok(())
+   errors.into_result()
 }
 
 
 enum Ac { Value, Index, Key } // accessor
 
+// list indices and keys from included files aren't always identifiers
+pub fn key_item(key: &str, span: Span) -> Rc<Item> {
+   if let Ok(mut ident) = parse_str::<Ident>(key) {
+      ident.set_span(span);
+      Item::Ident(ident).into()
+   }
+   else {
+      let mut lit = match usize::from_str(key) {
+         Ok(index) => Literal::usize_unsuffixed(index),
+         Err(_) => Literal::string(key),
+      };
+      lit.set_span(span);
+      Item::Literal(lit).into()
+   }
+}
+
+// `path ?? default`
+fn split_default(item_path: Group) -> Res<(Group, Option<TokenStream>)> {
+
+   let tokens: Vec<TokenTree> = item_path.stream().into_iter().collect();
+
+   let split = tokens.windows(2).position(|pair| matches!(pair,
+      [TokenTree::Punct(a), TokenTree::Punct(b)] if a.as_char() == '?' && a.spacing() == Spacing::Joint && b.as_char() == '?'
+   ));
+
+   match split {
+      Some(split) => {
+         if split + 2 == tokens.len() { err!(tokens[split + 1].span(), "expected a default value after `??`") }
+         let mut path = Group::new(item_path.delimiter(), tokens[..split].iter().cloned().collect());
+         path.set_span(item_path.span());
+         Ok((path, Some(tokens[split + 2..].iter().cloned().collect())))
+      },
+      None => Ok((item_path, None)),
+   }
+}
+
+// `path?` quotes nothing when missing, `path ?? default` evaluates the default like an assigned value
 pub fn parse_item_path(item_path: Group, env: &mut Env) -> Res<Rc<Item>> {
+   Ok(lookup_item_path(item_path, env)?.unwrap_or_else(|| Item::Stream(TokenStream::new()).into()))
+}
+
+// where items are counted or iterated a missing one is an empty list
+pub fn parse_list_path(item_path: Group, env: &mut Env) -> Res<Rc<Item>> {
+   Ok(lookup_item_path(item_path, env)?.unwrap_or_else(|| Item::List(Vec::new()).into()))
+}
+
+fn lookup_item_path(item_path: Group, env: &mut Env) -> Res<Option<Rc<Item>>> {
+
+   let span = item_path.span();
+   let (path, default) = split_default(item_path)?;
+
+   match (find_item_path(path, default.is_some(), env)?, default) {
+      (Some(item), _) => Ok(Some(item)),
+      (None, Some(default)) => {
+         let mut input = TokenIter::from(default);
+         let assign = parse_assign_value(span, &mut input, env)?;
+         if let Some(token) = input.next() { err!(token.span(), "unexpected token") }
+         Ok(Some(parse_assign(assign, env)?))
+      },
+      (None, None) => Ok(None),
+   }
+}
+
+// `list.0.1` and `list.1.-1` are lexed with float literals
+fn split_float(token: TokenTree) -> Vec<TokenTree> {
+   if let TokenTree::Literal(lit) = &token
+      && let Some((a, b)) = lit.to_string().split_once('.')
+      && !a.is_empty() && (a.to_string() + b).chars().all(|ch| ch.is_ascii_digit())
+   {
+      return [a, ".", b].into_iter().filter(|part| !part.is_empty()).map(|part| {
+         let mut token = match part {
+            "." => TokenTree::from(Punct::new('.', Spacing::Alone)),
+            index => TokenTree::from(Literal::from_str(index).unwrap()), // digits only
+         };
+         token.set_span(lit.span());
+         token
+      }).collect()
+   }
+   vec![token]
+}
+
+// unsuffixed integer, optionally negative
+fn parse_index(span: Span, lit: &Literal, negative: bool) -> Res<isize> {
+   match isize::from_str(&lit.to_string()) {
+      Ok(index) => Ok(if negative { -index } else { index }),
+      Err(_) => err!(span, "unexpected token"),
+   }
+}
+
+// slice end after `..`, `..3`, `..-1` or `..=3`
+fn parse_slice_end(mut span: Span, item_path: &mut Peekable<IntoIter>) -> Res<(Span, Option<isize>)> {
+
+   let inclusive = matches!(item_path.peek(), Some(TokenTree::Punct(pt)) if pt.as_char() == '=');
+   if inclusive { span = join(span, next!(span, item_path).span()) }
+
+   let negative = matches!(item_path.peek(), Some(TokenTree::Punct(pt)) if pt.as_char() == '-');
+   if negative { span = join(span, next!(span, item_path).span()) }
+
+   let end = match item_path.peek() {
+      Some(TokenTree::Literal(_)) => {
+         let lit = match_next!(span, item_path, Literal);
+         span = join(span, lit.span());
+         Some(parse_index(span, &lit, negative)?)
+      },
+      _ if negative || inclusive => err!(span, "expected an end index"),
+      _ => None,
+   };
+
+   Ok((span, match (end, inclusive) {
+      (Some(-1), true) => None,
+      (Some(end), true) => Some(end + 1),
+      (end, _) => end,
+   }))
+}
+
+// missing items give `None` if optional or marked with a trailing `?`
+pub fn find_item_path(item_path: Group, mut optional: bool, env: &mut Env) -> Res<Option<Rc<Item>>> {
 
-   // let full_span = item_path.span();
    let mut span = item_path.span();
-   let mut item_path = item_path.stream().into_iter();
+   let mut item_path = item_path.stream().into_iter().flat_map(split_float).collect::<TokenStream>().into_iter().peekable();
 
    let mut path = Vec::new();
    let mut needs_segment = true;
@@ -88,24 +307,84 @@ pub fn parse_item_path(item_path: Group, env: &mut Env) -> Res<Rc<Item>> {
 
          TokenTree::Punct(punct) => match punct.as_char() {
 
+            // slice, `list..2` or with the preceding index as start, `list.1..3`
+            '.' if !needs_segment && matches!(item_path.peek(), Some(TokenTree::Punct(pt)) if pt.as_char() == '.') => {
+               span = join(span, next!(span, item_path).span());
+               let start = match path.last() {
+                  Some(Segment { key: Key::Index(index), span: start_span }) => {
+                     span = join(*start_span, span);
+                     let index = *index;
+                     path.pop();
+                     Some(index)
+                  },
+                  _ => None,
+               };
+               let (span, end) = parse_slice_end(span, &mut item_path)?;
+               path.push(Segment { span, key: Key::Slice(start, end) });
+            },
+
             // access sub
             '.' if !needs_segment => needs_segment = true,
 
+            // negative index, `list.-1`
+            '-' if needs_segment => {
+               let lit = match_next!(span, item_path, Literal);
+               span = join(punct.span(), lit.span());
+               needs_segment = false;
+               path.push(Segment { span, key: Key::Index(parse_index(span, &lit, true)?) });
+            },
+
+            // optional access
+            '?' if !needs_segment && item_path.peek().is_none() => optional = true,
+
             // access bind
-            '@' if item.is_none() && path.len() == 0 => {
+            '@' if item.is_none() && path.is_empty() => {
 
                needs_segment = false;
 
-               let mut id_span = span;
-               let ident = match_next!(id_span, item_path, Ident);
-               span = span.join(id_span).unwrap();
+               let (level, ident) = match next!(span, item_path) {
+
+                  // function call
+                  TokenTree::Ident(func) if matches!(item_path.peek(), Some(TokenTree::Group(gp)) if matches!(gp.delimiter(), Parenthesis | Brace)) => {
+                     let args = match_next!(span, item_path, Group);
+                     item = Some((join(punct.span(), span), call_function(func, args, env)?));
+                     continue;
+                  },
+
+                  // outer iteration scope by level, `@1.index`
+                  TokenTree::Literal(lit) => match usize::from_str(&lit.to_string()) {
+                     Ok(level) => {
+                        match_next!(span, item_path, Punct(pt) if pt.as_char() == '.');
+                        (level, match_next!(span, item_path, Ident))
+                     },
+                     Err(_) => err!(span, "unexpected token"),
+                  },
+
+                  // outer iteration scopes, `@outer.outer.index`
+                  TokenTree::Ident(mut ident) => {
+                     let mut level = 0;
+                     while ident == "outer" {
+                        level += 1;
+                        match_next!(span, item_path, Punct(pt) if pt.as_char() == '.');
+                        ident = match_next!(span, item_path, Ident);
+                     }
+                     (level, ident)
+                  },
+
+                  _ => err!(span, "unexpected token"),
+               };
+
+               span = join(punct.span(), span);
 
                let get = match ident.to_string().as_str() {
                   "value" => Ac::Value, "index" => Ac::Index, "key" => Ac::Key,
-                  _ => err!(span, "unknown identifier"),
+                  _ => err!(span, "unknown accessor `@{}`, expected `@value`, `@index` or `@key`", ident),
                };
 
-               let scope = if let Some(scp) = env.get_iter_scope() { scp }
+               let scope = if let Some(scp) = env.get_outer_iter_scope(level) { scp }
+               else if level > 0 {
+                  err!(span, "there is no iteration block {} level{} outside of this one", level, if level == 1 { "" } else { "s" })
+               }
                else { match get {
                   Ac::Value => err!(span, "@value is only available in iterator blocks"),
                   Ac::Index => err!(span, "@index is only available in iterator blocks"),
@@ -115,7 +394,7 @@ pub fn parse_item_path(item_path: Group, env: &mut Env) -> Res<Rc<Item>> {
                item = Some((span, match get {
                   Ac::Value => Rc::clone(&scope.value),
                   Ac::Index => Item::Literal(Literal::usize_unsuffixed(scope.index)).into(),
-                  Ac::Key => Item::Ident(Ident::new(&scope.key, span)).into(),
+                  Ac::Key => key_item(&scope.key, span),
                }));
             },
 
@@ -127,12 +406,9 @@ pub fn parse_item_path(item_path: Group, env: &mut Env) -> Res<Rc<Item>> {
             path.push(Segment { span, key: Key::String(ident.to_string()) });
          },
 
-         TokenTree::Literal(lit) if needs_segment => match usize::from_str(&lit.to_string()) {
-            Ok(index) => {
-               needs_segment = false;
-               path.push(Segment { span, key: Key::Index(index) });
-            },
-            Err(_) => err!(span, "unexpected token"),
+         TokenTree::Literal(lit) if needs_segment => {
+            needs_segment = false;
+            path.push(Segment { span, key: Key::Index(parse_index(span, &lit, false)?) });
          },
 
          _ => err!(span, "unexpected token"),
@@ -140,8 +416,8 @@ pub fn parse_item_path(item_path: Group, env: &mut Env) -> Res<Rc<Item>> {
    }
 
    if let Some((span, item)) = item {
-      item.get_item(span, &path)
+      item.lookup(span, &path, optional)
    } else {
-      env.get_item(&path)
+      env.lookup(&path, optional)
    }
 }
\ No newline at end of file
@@ -0,0 +1,128 @@
+use proc_macro2::{TokenStream, TokenTree, Group, Ident, Span, Delimiter::Parenthesis};
+use std::rc::Rc;
+
+use crate::*;
+
+
+// loop variables, `x`, `_` or `(a, (b, c))`
+pub enum Pattern {
+   Bind(Ident),
+   Ignore,
+   Tuple(Span, Vec<Pattern>),
+}
+
+
+fn parse_pattern(token: TokenTree) -> Res<Pattern> {
+   Ok(match token {
+
+      TokenTree::Ident(ident) if ident == "_" => Pattern::Ignore,
+      TokenTree::Ident(ident) => Pattern::Bind(ident),
+
+      TokenTree::Group(gp) if gp.delimiter() == Parenthesis => {
+
+         let mut list = Vec::new();
+         let mut tokens = gp.stream().into_iter();
+
+         while let Some(token) = tokens.next() {
+
+            list.push(parse_pattern(token)?);
+
+            match tokens.next() {
+               None => break,
+               Some(TokenTree::Punct(pt)) if pt.as_char() == ',' => continue,
+               Some(other) => err!(other.span(), "unexpected token"),
+            }
+         }
+
+         Pattern::Tuple(gp.span(), list)
+      },
+
+      other => err!(other.span(), "expected an identifier or a `(...)` pattern"),
+   })
+}
+
+
+// `[pattern in] path [where condition]`
+pub struct IterHead {
+   pub pattern: Option<Pattern>,
+   pub path: Group,
+   pub filter: Option<Group>,
+}
+
+fn sub_group(head: &Group, tokens: Vec<TokenTree>) -> Group {
+   let mut group = Group::new(head.delimiter(), tokens.into_iter().collect::<TokenStream>());
+   group.set_span(head.span());
+   group
+}
+
+fn position_of(tokens: &[TokenTree], keyword: &str) -> Option<usize> {
+   tokens.iter().position(|token| matches!(token, TokenTree::Ident(id) if id == keyword))
+}
+
+pub fn parse_iter_head(head: Group) -> Res<IterHead> {
+
+   let mut tokens: Vec<TokenTree> = head.stream().into_iter().collect();
+
+   let filter = match position_of(&tokens, "where") {
+      Some(split) => {
+         let cond = tokens.split_off(split + 1);
+         let keyword = tokens.pop().unwrap(); // `where`
+         if cond.is_empty() { err!(keyword.span(), "expected a condition after `where`") }
+         Some(sub_group(&head, cond))
+      },
+      None => None,
+   };
+
+   let pattern = match position_of(&tokens, "in") {
+      Some(split) => {
+         let path = tokens.split_off(split + 1);
+         let mut pattern = std::mem::replace(&mut tokens, path).into_iter();
+         match (pattern.next(), pattern.next(), pattern.next()) {
+            (Some(token), Some(_), None) => Some(parse_pattern(token)?),
+            (Some(_), Some(token), Some(_)) => err!(token.span(), "unexpected token"),
+            _ => err!(head.span(), "expected a pattern before `in`"),
+         }
+      },
+      None => None,
+   };
+
+   Ok(IterHead { pattern, path: sub_group(&head, tokens), filter })
+}
+
+
+impl Pattern {
+
+   pub fn bind(&self, item: Rc<Item>, bindings: &mut Vec<(String, Rc<Item>)>) -> Res<()> {
+      match self {
+
+         Pattern::Bind(ident) => bindings.push((ident.to_string(), item)),
+
+         Pattern::Ignore => {},
+
+         Pattern::Tuple(span, patterns) => match item.as_ref() {
+
+            Item::List(list) if list.len() == patterns.len() => {
+               for (pattern, item) in patterns.iter().zip(list) {
+                  pattern.bind(Rc::clone(item), bindings)?;
+               }
+            },
+
+            Item::List(list) => err!(*span, "expected a list of {} items, found one with {}", patterns.len(), list.len()),
+
+            other => err!(*span, "can't destructure a {} item", other.kind()),
+         },
+      }
+      Ok(())
+   }
+
+   // maps bind `(key, value)` pairs
+   pub fn bind_entry(&self, key: Rc<Item>, value: Rc<Item>, bindings: &mut Vec<(String, Rc<Item>)>) -> Res<()> {
+      match self {
+         Pattern::Tuple(_, patterns) if patterns.len() == 2 => {
+            patterns[0].bind(key, bindings)?;
+            patterns[1].bind(value, bindings)
+         },
+         _ => self.bind(value, bindings),
+      }
+   }
+}
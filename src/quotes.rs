@@ -1,25 +1,37 @@
 
 use proc_macro2::{TokenStream, TokenTree, Ident, Group, Span};
-use syn::parse_str;
-use itertools::Either;
+use syn::{parse_str, LitStr, LitChar};
 use std::rc::Rc;
+use std::str::FromStr;
 
 use crate::*;
 
 
-pub enum BlockModifier { Concat, First, Last, NotFirst, NotLast }
-pub enum ItemModifier { None, Len }
+pub enum BlockModifier { Concat, Case(Case), Str(StrKind), First, Last, NotFirst, NotLast }
+pub enum ItemModifier { None, Len, Case(Case), Stringify }
+pub enum StrKind { Plain, Raw, Byte, Stringify }
+pub enum IterModifier { None, Sorted }
+
+// `sep {..}` between iterations, `sep trailing {..}` after each
+pub struct Separator { pub block: Group, pub trailing: bool }
 
 pub enum Quote {
    Block(BlockModifier, Group),
-   Iter(Group, Group),
+   Iter(IterModifier, Group, Group, Option<Separator>, Option<Group>), // with fallback if empty
    Item(ItemModifier, Group),
+   If(Vec<(Group, Group)>, Option<Group>),
+   Expr(Group),
+   Call(Ident, Group),
 }
 
 impl Quote {
    fn span(&self) -> Span {
       match self {
-         Quote::Block(_, blk) => blk.span(), Quote::Iter(_, blk) => blk.span(), Quote::Item(_, gp) => gp.span()
+         Quote::Block(_, blk) => blk.span(), Quote::Item(_, gp) => gp.span(),
+         Quote::Iter(_, _, _, _, Some(blk)) => blk.span(), Quote::Iter(_, _, _, Some(sep), None) => sep.block.span(),
+         Quote::Iter(_, _, blk, None, None) => blk.span(),
+         Quote::Expr(gp) => gp.span(), Quote::Call(_, args) => args.span(),
+         Quote::If(branches, otherwise) => otherwise.as_ref().unwrap_or(&branches[branches.len() - 1].1).span(),
       }
    }
 }
@@ -33,15 +45,187 @@ fn parse_scoped_block(input: TokenStream, output: &mut TokenStream, env: &mut En
 }
 
 
+pub fn with_iter_scope<T>(
+   env: &mut Env, iter_scope: IterScope, bindings: Vec<(String, Rc<Item>)>, f: impl FnOnce(&mut Env) -> Res<T>,
+) -> Res<T> {
+   env.push_scope(Some(iter_scope));
+   for (name, item) in bindings { env.set_item(name, item) }
+   let res = f(env);
+   env.pop_scope();
+   res
+}
+
+
+// evaluated block as continuous text
+fn evaluate_text(input: TokenStream, env: &mut Env) -> Res<String> {
+   Ok(evaluate_parts(input, env)?.concat())
+}
+
+// evaluated block as the text of each token
+fn evaluate_parts(input: TokenStream, env: &mut Env) -> Res<Vec<String>> {
+   let mut collector = TokenStream::new();
+   parse_scoped_block(input, &mut collector, env, None)?;
+   Ok(collector.into_iter().map(token_text).collect())
+}
+
+// the text of each token, string literals give their value
+fn item_parts(span: Span, item: &Item) -> Res<Vec<String>> {
+   match item {
+      Item::Literal(lit) => Ok(vec![token_text(lit.clone().into())]),
+      Item::Ident(ident) => Ok(vec![ident.to_string()]),
+      Item::Stream(stream) => Ok(stream.clone().into_iter().map(token_text).collect()),
+      _ => err!(span, "expected an identifier, found a {} item", item.kind()),
+   }
+}
+
+// like `concat!`, literals contribute their value
+fn token_text(token: TokenTree) -> String {
+   match token {
+      TokenTree::Literal(lit) => {
+         let string = lit.to_string();
+         if let Ok(lit) = parse_str::<LitStr>(&string) { lit.value() }
+         else if let Ok(lit) = parse_str::<LitChar>(&string) { lit.value().to_string() }
+         else { string }
+      },
+      TokenTree::Ident(ident) => ident.to_string(),
+      TokenTree::Punct(punct) => punct.as_char().to_string(),
+      TokenTree::Group(group) => group.to_string(),
+   }
+}
+
+// no spaces are inserted, raw identifiers lose their `r#`
+fn concat_text(input: TokenStream) -> String {
+   input.into_iter().map(|token| match token {
+      TokenTree::Ident(ident) => {
+         let text = ident.to_string();
+         text.strip_prefix("r#").map_or(text.clone(), str::to_string)
+      },
+      other => token_text(other),
+   }).collect()
+}
+
+// with enough hashes to contain any `"#` sequence
+fn raw_string(text: &str) -> Literal {
+   let mut hashes = String::from("#");
+   while text.contains(&format!("\"{}", hashes)) { hashes.push('#'); }
+   Literal::from_str(&format!("r{}\"{}\"{}", hashes, text, hashes)).unwrap() // valid by construction
+}
+
+fn quote_ident(span: Span, err_span: Span, text: &str, output: &mut TokenStream) -> Res<()> {
+
+   let mut ident = match parse_str::<Ident>(text) {
+      Ok(ident) => ident,
+      Err(_) => err!(err_span, "`{}` is not a valid identifier", text),
+   };
+
+   ident.set_span(span);
+   output.extend(Some(TokenTree::from(ident)));
+
+   Ok(())
+}
+
+// kebab case can't form identifiers, so it results in a string literal, keywords become raw identifiers
+fn quote_case(span: Span, err_span: Span, case: Case, text: &str, output: &mut TokenStream) -> Res<()> {
+   if let Case::Kebab = case {
+      let mut literal = Literal::string(text);
+      literal.set_span(span);
+      output.extend(Some(TokenTree::from(literal)));
+      Ok(())
+   }
+   else if parse_str::<Ident>(text).is_err() && parse_str::<Ident>(&format!("r#{}", text)).is_ok() {
+      quote_ident(span, err_span, &format!("r#{}", text), output)
+   }
+   else {
+      quote_ident(span, err_span, text, output)
+   }
+}
+
+
+fn quote_item(span: Span, item: &Item, output: &mut TokenStream) -> Res<()> {
+   match item {
+
+      Item::Ident(ident) => {
+         let mut ident = ident.clone();
+         ident.set_span(span);
+         output.extend(Some(TokenTree::from(ident)));
+      },
+
+      Item::Literal(literal) => {
+         let mut literal = literal.clone();
+         literal.set_span(span);
+         output.extend(Some(TokenTree::from(literal)));
+      },
+
+      Item::Stream(stream) => output.extend(stream.clone()),
+
+      Item::List(_) | Item::Map(_) | Item::Range(_) => return Err(
+         Error::new(span, format!("can not quote a {} item", item.kind()))
+         .help("iterate over it with `$[...]{...}` or quote one of its elements")
+      ),
+
+      Item::Def(def) => return Err(
+         Error::new(span, format!("can not quote a {} item", item.kind()))
+         .help(format!("call it with `${}!(...)`", def.name))
+      ),
+   }
+   Ok(())
+}
+
+
+// nested scopes, mostly from recursive calls
+const MAX_DEPTH: usize = 256;
+
+// evaluate the definition body where it was defined, with the arguments bound
+fn call_template(span: Span, name: Ident, args: Group, output: &mut TokenStream, env: &mut Env) -> Res<()> {
+
+   let item = env.get_item(&[Segment { span: name.span(), key: Key::String(name.to_string()) }])?;
+
+   let def = match item.as_ref() {
+      Item::Def(def) => def,
+      other => err!(name.span(), "expected a template, found a {} item", other.kind()),
+   };
+
+   let args = match parse_assign(Assign::List(args), env)?.as_ref() {
+      Item::List(list) => list.clone(),
+      _ => unreachable!(),
+   };
+
+   if args.len() != def.params.len() {
+      return Err(
+         Error::new(span, format!(
+            "template `{}` takes {} argument{} but {} {} supplied",
+            name, def.params.len(), if def.params.len() == 1 { "" } else { "s" },
+            args.len(), if args.len() == 1 { "was" } else { "were" },
+         ))
+         .note_at(def.name.span(), "defined here")
+      )
+   }
+
+   if env.calls() >= MAX_DEPTH {
+      err!(span, "recursion limit reached while calling template `{}`", name)
+   }
+
+   // also bound to itself for recursion from within a block
+   let mut items = def.scope.clone();
+   items.insert(def.name.to_string(), Rc::clone(&item));
+
+   for (param, arg) in def.params.iter().zip(args) {
+      items.insert(param.to_string(), arg);
+   }
+
+   env.with_call_scope(items, |env| parse_block(def.body.stream(), output, env))
+}
+
+
 pub fn parse_quote(span: Span, quote: Quote, output: &mut TokenStream, env: &mut Env) -> Res<()> {
 
-   let span = span.join(quote.span()).unwrap();
+   let span = join(span, quote.span());
 
    match quote {
 
       Quote::Block(modifier, block) => {
 
-         use BlockModifier::{First, Last, NotFirst, NotLast, Concat};
+         use BlockModifier::{First, Last, NotFirst, NotLast, Concat, Case, Str};
 
          if matches!(modifier, First | Last | NotFirst | NotLast) {
             if let Some(scope) = env.get_iter_scope() {
@@ -57,104 +241,154 @@ pub fn parse_quote(span: Span, quote: Quote, output: &mut TokenStream, env: &mut
             }
          }
 
-         if let Concat = modifier {
+         match modifier {
+
+            Concat => {
+               let text = evaluate_text(block.stream(), env)?;
+               quote_ident(span, block.span(), &text, output)?;
+            },
 
-            let mut collector = TokenStream::new();
-            parse_scoped_block(block.stream(), &mut collector, env, None)?;
+            Case(case) => {
+               let text = case.convert(&evaluate_parts(block.stream(), env)?);
+               quote_case(span, block.span(), case, &text, output)?;
+            },
 
-            let ident_str = collector.to_string().replace(" ", "");
+            Str(kind) => {
 
-            let mut ident = match parse_str::<Ident>(&ident_str) {
-               Ok(ident) => ident,
-               Err(_) => err!(block.span(), "this doesn't concatenate to an identifier"),
-            };
+               let mut collector = TokenStream::new();
+               parse_scoped_block(block.stream(), &mut collector, env, None)?;
 
-            ident.set_span(span);
+               let mut literal = match kind {
+                  StrKind::Plain => Literal::string(&concat_text(collector)),
+                  StrKind::Raw => raw_string(&concat_text(collector)),
+                  StrKind::Byte => Literal::byte_string(concat_text(collector).as_bytes()),
+                  StrKind::Stringify => Literal::string(&stringify(collector)),
+               };
 
-            output.extend(Some(TokenTree::from(ident)));
-         }
-         else {
-            parse_scoped_block(block.stream(), output, env, None)?;
+               literal.set_span(span);
+               output.extend(Some(TokenTree::from(literal)));
+            },
+
+            _ => parse_scoped_block(block.stream(), output, env, None)?,
          }
       },
 
-      Quote::Iter(path_group, block) => {
+      Quote::Iter(modifier, head, block, separator, fallback) => {
 
-         let item = parse_item_path(path_group, env)?;
+         let IterHead { pattern, path, filter } = parse_iter_head(head)?;
 
-         if let Item::Map(map) = item.as_ref() {
+         let (_, item) = arg_item(path.span(), path.stream(), env)?;
 
-            let item_iter = map.iter();
-            let last = item_iter.len() - 1;
+         let mut entries: Vec<(String, Rc<Item>)> = match item.as_ref() {
+            Item::Map(map) => map.iter().map(|(key, item)| (key.clone(), Rc::clone(item))).collect(),
+            Item::Ident(_) | Item::Literal(_) | Item::Stream(_) => vec![(0.to_string(), Rc::clone(&item))],
+            Item::List(list) => list.iter().enumerate().map(|(i, item)| (i.to_string(), Rc::clone(item))).collect(),
+            Item::Range(range) => range.iter().enumerate().map(|(i, lit)| (i.to_string(), Item::Literal(lit).into())).collect(),
+            Item::Def(_) => err!(span, "can not iterate over a {} item", item.kind()),
+         };
 
-            for (i, (key, item)) in item_iter.enumerate() {
-
-               let iter_scope = IterScope {
-                  first: i == 0, last: i == last, index: i, key: key.to_string(), value: Rc::clone(item),
-               };
+         let is_map = matches!(item.as_ref(), Item::Map(_));
 
-               parse_scoped_block(block.stream(), output, env, Some(iter_scope))?;
+         // maps by key, lists like `@sort` and indexed by their new position
+         if let IterModifier::Sorted = modifier {
+            if is_map {
+               entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+            }
+            else {
+               entries.sort_by(|(_, a), (_, b)| compare(a, b));
+               for (i, (key, _)) in entries.iter_mut().enumerate() { *key = i.to_string() }
             }
          }
-         else {
-
-            let item_iter = match item.as_ref() {
-               Item::Ident(_) | Item::Literal(_) | Item::Stream(_) => {
-                  Either::Left([Rc::clone(&item)].into_iter())
-               },
-               Item::List(list) => {
-                  Either::Right(list.iter().cloned())
-               },
-               _ => unreachable!(),
-            };
-            let last = item_iter.len() - 1;
 
-            for (i, item) in item_iter.enumerate() {
+         let mut selected = Vec::with_capacity(entries.len());
 
-               let iter_scope = IterScope {
-                  first: i == 0, last: i == last, index: i, key: i.to_string(), value: item,
-               };
+         for (i, (key, item)) in entries.into_iter().enumerate() {
 
-               parse_scoped_block(block.stream(), output, env, Some(iter_scope))?;
+            let mut bindings = Vec::new();
+
+            match &pattern {
+               Some(pattern) if is_map => pattern.bind_entry(key_item(&key, span), Rc::clone(&item), &mut bindings)?,
+               Some(pattern) => pattern.bind(Rc::clone(&item), &mut bindings)?,
+               None => {},
+            }
+
+            // filtered before first, last and index are known
+            if let Some(filter) = &filter {
+               let iter_scope = IterScope { first: false, last: false, index: i, key: key.clone(), value: Rc::clone(&item) };
+               let keep = with_iter_scope(env, iter_scope, bindings.clone(), |env| evaluate_condition(filter.clone(), env))?;
+               if !keep { continue }
             }
+
+            selected.push((key, item, bindings));
          }
-      },
 
-      Quote::Item(modifier, path_group) => match modifier {
+         if selected.is_empty() {
+            if let Some(fallback) = fallback {
+               parse_scoped_block(fallback.stream(), output, env, None)?;
+            }
+            return Ok(())
+         }
 
-         ItemModifier::None => match parse_item_path(path_group, env)?.as_ref() {
+         let last = selected.len() - 1;
 
-            Item::Ident(ident) => {
-               let mut ident = ident.clone();
-               ident.set_span(span);
-               output.extend(Some(TokenTree::from(ident)));
-            },
+         for (i, (key, item, bindings)) in selected.into_iter().enumerate() {
+            let iter_scope = IterScope { first: i == 0, last: i == last, index: i, key, value: item };
+            let separate = separator.as_ref().filter(|sep| i != last || sep.trailing);
+            with_iter_scope(env, iter_scope, bindings, |env| {
+               parse_block(block.stream(), output, env)?;
+               match separate {
+                  Some(sep) => parse_block(sep.block.stream(), output, env),
+                  None => Ok(()),
+               }
+            })?;
+         }
+      },
 
-            Item::Literal(literal) => {
-               let mut literal = literal.clone();
-               literal.set_span(span);
-               output.extend(Some(TokenTree::from(literal)));
-            },
+      Quote::Item(modifier, path_group) => match modifier {
 
-            Item::Stream(stream) => output.extend(stream.clone().into_iter()),
+         ItemModifier::None => quote_item(span, parse_item_path(path_group, env)?.as_ref(), output)?,
 
-            Item::List(_) => err!(span, "can not quote a list item"),
-            Item::Map(_) => err!(span, "can not quote a map item"),
+         ItemModifier::Case(case) => {
+            let text = case.convert(&item_parts(span, parse_item_path(path_group.clone(), env)?.as_ref())?);
+            quote_case(span, path_group.span(), case, &text, output)?;
          },
 
-         ItemModifier::Len => {
-
-            let len = match parse_item_path(path_group, env)?.as_ref() {
-               Item::Ident(_) | Item::Literal(_) | Item::Stream(_) => 1,
-               Item::List(list) => list.len(),
-               Item::Map(map) => map.len(),
+         ItemModifier::Stringify => {
+            let mut literal = match parse_item_path(path_group, env)?.as_ref() {
+               Item::Stream(stream) => Literal::string(&stringify(stream.clone())),
+               item @ (Item::Ident(_) | Item::Literal(_)) => Literal::string(&item.to_token_string()),
+               item => err!(span, "can not stringify a {} item", item.kind()),
             };
+            literal.set_span(span);
+            output.extend(Some(TokenTree::from(literal)));
+         },
 
-            let mut literal = Literal::usize_unsuffixed(len);
+         ItemModifier::Len => {
+            let mut literal = Literal::usize_unsuffixed(parse_list_path(path_group, env)?.len());
             literal.set_span(span);
             output.extend(Some(TokenTree::from(literal)));
          },
-      }
+      },
+
+      Quote::Expr(expr) => quote_item(span, evaluate_expression(expr, env)?.as_ref(), output)?,
+
+      Quote::Call(name, args) => call_template(span, name, args, output, env)?,
+
+      Quote::If(branches, otherwise) => {
+
+         let mut block = otherwise;
+
+         for (cond, blk) in branches {
+            if evaluate_condition(cond, env)? {
+               block = Some(blk);
+               break;
+            }
+         }
+
+         if let Some(block) = block {
+            parse_scoped_block(block.stream(), output, env, None)?;
+         }
+      },
    }
 
    Ok(())
@@ -0,0 +1,189 @@
+use proc_macro2::{TokenStream, TokenTree, Spacing, Delimiter};
+
+
+// tokens with joint punctuation merged into operators
+enum Tk {
+   Word(String),
+   Op(String),
+   Group(Delimiter, String),
+}
+
+
+// multi-char operators, longest first
+const JOINED_OPS: [&str; 24] = [
+   "<<=", ">>=", "...", "..=",
+   "::", "->", "=>", "==", "!=", "<=", ">=", "&&", "||", "+=", "-=", "*=", "/=", "%=", "^=", "&=", "|=", "<<", ">>", "..",
+];
+
+// split a run of joint punctuation, e.g. `?;` stays two tokens
+fn push_ops(run: &str, tokens: &mut Vec<Tk>) {
+   let mut rest = run;
+   while let Some(ch) = rest.chars().next() {
+      let len = JOINED_OPS.iter().find(|op| rest.starts_with(*op)).map_or(ch.len_utf8(), |op| op.len());
+      tokens.push(Tk::Op(rest[..len].to_string()));
+      rest = &rest[len..];
+   }
+}
+
+fn merge(input: TokenStream) -> Vec<Tk> {
+
+   let mut tokens = Vec::new();
+   let mut run = String::new();
+
+   for token in input {
+      match token {
+         TokenTree::Punct(punct) => {
+            run.push(punct.as_char());
+            if punct.spacing() == Spacing::Alone {
+               push_ops(&std::mem::take(&mut run), &mut tokens);
+            }
+         },
+         // lifetimes
+         TokenTree::Ident(ident) if run.ends_with('\'') => {
+            run.pop();
+            push_ops(&std::mem::take(&mut run), &mut tokens);
+            tokens.push(Tk::Word(format!("'{}", ident)));
+         },
+         other => {
+            push_ops(&std::mem::take(&mut run), &mut tokens);
+            match other {
+               TokenTree::Group(group) => tokens.push(Tk::Group(group.delimiter(), stringify(group.stream()))),
+               other => tokens.push(Tk::Word(other.to_string())),
+            }
+         },
+      }
+   }
+
+   push_ops(&run, &mut tokens);
+
+   tokens
+}
+
+
+// keywords after which an operator starts an expression or a type, e.g. `return -1` or `as *const u8`
+const PREFIX_AFTER: [&str; 13] = ["return", "break", "in", "if", "while", "match", "else", "as", "mut", "move", "let", "for", "impl"];
+
+fn is_literal(word: &str) -> bool {
+   word.starts_with(|ch: char| ch.is_ascii_digit() || ch == '"') || word.starts_with("b'") || word.starts_with("b\"")
+      || (word.starts_with('\'') && word.ends_with('\'') && word.len() > 2)
+}
+
+// an operator here is unary, like `-` in `f(-1)` or `&` in `x: &u8`
+fn starts_operand(prev: Option<&Tk>, prev_generic: bool) -> bool {
+   match prev {
+      None => true,
+      Some(Tk::Word(word)) => PREFIX_AFTER.contains(&word.as_str()),
+      Some(Tk::Op(op)) => !((prev_generic && op.starts_with('>')) || op == "?"),
+      Some(Tk::Group(delim, _)) => *delim == Delimiter::Brace,
+   }
+}
+
+
+// `<` first or after a word opens generics if it is closed before anything only expressions contain
+fn opens_generics(tokens: &[Tk], at: usize) -> bool {
+
+   let before = match at.checked_sub(1).map(|i| &tokens[i]) {
+      None => None,
+      Some(Tk::Word(word)) if !is_literal(word) => Some(word.as_str()),
+      Some(Tk::Op(op)) if op == "::" => None,
+      _ => return false,
+   };
+
+   let mut depth = 0;
+
+   for (i, tk) in tokens.iter().enumerate().skip(at) {
+      match tk {
+         Tk::Op(op) if op == "<" => depth += 1,
+         Tk::Op(op) if op.chars().all(|ch| ch == '>') => {
+            if op.len() >= depth {
+               // `a < b, c > d` is followed by an operand, `impl<T> Foo` by a type
+               return match tokens.get(i + 1) {
+                  Some(Tk::Word(word)) => matches!(word.as_str(), "where" | "for" | "as") || matches!(before, Some("impl" | "for")),
+                  _ => true,
+               }
+            }
+            depth -= op.len();
+         },
+         Tk::Op(op) if matches!(op.as_str(), ";" | "&&" | "||" | "==" | "!=" | "<=" | ">=" | "<<" | "<<=" | ">>=") => return false,
+         Tk::Group(Delimiter::Brace, _) => return false,
+         _ => {},
+      }
+   }
+
+   false
+}
+
+
+// approximates rustfmt spacing as rustc's `stringify!` shows it, e.g. `Vec<Option<u8>>`, `-x * y` or `|x| x + 1`
+pub fn stringify(input: TokenStream) -> String {
+
+   let tokens = merge(input);
+
+   let mut text = String::new();
+   let mut prev: Option<&Tk> = None;
+   let mut prev_generic = false; // previous token opened or closed generics
+   let mut prev_tight = false; // previous token binds to the next one, e.g. unary operators
+   let mut closure_params = false;
+   let mut angle_depth = 0;
+
+   for (i, tk) in tokens.iter().enumerate() {
+
+      let op = match tk { Tk::Op(op) => op.as_str(), _ => "" };
+      let operand = starts_operand(prev, prev_generic);
+
+      let generic_open = op == "<" && (angle_depth > 0 || opens_generics(&tokens, i));
+      let generic_close = !op.is_empty() && op.chars().all(|ch| ch == '>') && angle_depth > 0;
+
+      let bang = op == "!" && matches!(prev, Some(Tk::Word(word)) if !PREFIX_AFTER.contains(&word.as_str()));
+      let unary = operand && matches!(op, "-" | "*" | "&" | "&&" | "!" | "?");
+      let params_open = operand && op == "|" && !closure_params;
+      let params_close = op == "|" && closure_params;
+
+      let range = |tk: &Tk| matches!(tk, Tk::Op(op) if matches!(op.as_str(), ".." | "..=" | "..."));
+
+      let space = match (prev, tk) {
+         (None, _) => false,
+         _ if prev_tight || params_close || bang => false,
+         (Some(Tk::Word(_) | Tk::Group(..)), tk) if range(tk) => false,
+         (Some(tk), _) if range(tk) => false,
+         (_, Tk::Op(op)) if matches!(op.as_str(), "," | ";" | "." | ":" | "::") => false,
+         (_, Tk::Op(op)) if op == "?" && !unary => false,
+         (Some(Tk::Op(op)), _) if matches!(op.as_str(), "." | "::" | "#") => false,
+         (Some(Tk::Op(op)), _) if op == "<" && prev_generic => false,
+         (Some(Tk::Word(_) | Tk::Op(_)), Tk::Op(_)) if generic_open => matches!(prev, Some(Tk::Op(op)) if op != "::"),
+         (_, Tk::Op(_)) if generic_close => false,
+         (Some(Tk::Word(_) | Tk::Group(..)), Tk::Group(Delimiter::Parenthesis | Delimiter::Bracket, _)) => false,
+         (Some(Tk::Op(op)), Tk::Group(Delimiter::Parenthesis | Delimiter::Bracket, _)) if op.chars().all(|ch| ch == '>') && prev_generic => false,
+         _ => true,
+      };
+
+      if generic_open { angle_depth += 1 }
+      else if generic_close { angle_depth -= op.len().min(angle_depth) }
+
+      if params_open { closure_params = true }
+      else if params_close { closure_params = false }
+
+      prev_generic = generic_open || generic_close;
+      prev_tight = unary || bang || params_open;
+
+      if space { text.push(' ') }
+
+      match tk {
+         Tk::Word(word) | Tk::Op(word) => text.push_str(word),
+         Tk::Group(delim, inner) => match delim {
+            Delimiter::Parenthesis => { text.push('('); text.push_str(inner); text.push(')') },
+            Delimiter::Bracket => { text.push('['); text.push_str(inner); text.push(']') },
+            // use trees
+            Delimiter::Brace if inner.is_empty() || matches!(prev, Some(Tk::Op(op)) if op == "::") => {
+               text.push('{'); text.push_str(inner); text.push('}')
+            },
+            Delimiter::Brace => { text.push_str("{ "); text.push_str(inner); text.push_str(" }") },
+            Delimiter::None => text.push_str(inner),
+         },
+      }
+
+      prev = Some(tk);
+   }
+
+   text
+}
@@ -0,0 +1,304 @@
+use proc_macro2::{TokenStream, Ident, Literal, Span};
+use syn::{
+   parse2, parse::{Parser, ParseStream}, punctuated::Punctuated, spanned::Spanned, token::Comma,
+   Attribute, Data, DeriveInput, Fields, Generics, GenericParam, TypeParam, LifetimeParam, WherePredicate, Visibility, Signature, FnArg,
+   Item as SynItem, ItemTrait, ItemImpl, TraitItem, ImplItem, ForeignItemFn,
+};
+use quote::{quote, ToTokens};
+use indexmap::IndexMap;
+use std::rc::Rc;
+
+use crate::*;
+
+
+fn stream(tokens: impl ToTokens) -> Rc<Item> {
+   Item::Stream(tokens.to_token_stream()).into()
+}
+
+fn ident(name: &str, span: Span) -> Rc<Item> {
+   Item::Ident(Ident::new(name, span)).into()
+}
+
+fn flag(value: bool, span: Span) -> Rc<Item> {
+   ident(if value { "true" } else { "false" }, span)
+}
+
+fn map<'a>(entries: impl IntoIterator<Item=(&'a str, Rc<Item>)>) -> Rc<Item> {
+   Item::Map(entries.into_iter().map(|(key, item)| (key.to_string(), item)).collect::<IndexMap<_, _>>()).into()
+}
+
+fn attrs(attrs: &[Attribute]) -> Rc<Item> {
+   Item::List(attrs.iter().map(stream).collect()).into()
+}
+
+fn generics(generics: &Generics) -> [(&'static str, Rc<Item>); 2] {
+   [("generics", stream(generics)), ("where_clause", stream(&generics.where_clause))]
+}
+
+// tuple fields are named by their index
+fn fields(span: Span, fields: &Fields) -> [(&'static str, Rc<Item>); 2] {
+
+   let style = match fields {
+      Fields::Named(_) => "named", Fields::Unnamed(_) => "unnamed", Fields::Unit => "unit",
+   };
+
+   let list = fields.iter().enumerate().map(|(i, field)| map([
+      ("name", match &field.ident {
+         Some(name) => Item::Ident(name.clone()).into(),
+         None => {
+            let mut index = Literal::usize_unsuffixed(i);
+            index.set_span(span);
+            Item::Literal(index).into()
+         },
+      }),
+      ("ty", stream(&field.ty)),
+      ("vis", stream(&field.vis)),
+      ("attrs", attrs(&field.attrs)),
+   ])).collect();
+
+   [("style", ident(style, span)), ("fields", Item::List(list).into())]
+}
+
+
+// structs, enums and unions
+fn data_item(span: Span, input: DeriveInput) -> Rc<Item> {
+
+   let mut entries = vec![
+      ("kind", ident(match input.data { Data::Struct(_) => "struct", Data::Enum(_) => "enum", Data::Union(_) => "union" }, span)),
+      ("name", Item::Ident(input.ident.clone()).into()),
+      ("vis", stream(&input.vis)),
+      ("attrs", attrs(&input.attrs)),
+   ];
+
+   entries.extend(generics(&input.generics));
+
+   match &input.data {
+
+      Data::Struct(data) => entries.extend(fields(span, &data.fields)),
+
+      Data::Union(data) => entries.extend(fields(span, &Fields::Named(data.fields.clone()))),
+
+      // the discriminant is only present if given
+      Data::Enum(data) => entries.push(("variants", Item::List(data.variants.iter().map(|variant| {
+         let mut entries = vec![
+            ("name", Item::Ident(variant.ident.clone()).into()),
+            ("attrs", attrs(&variant.attrs)),
+         ];
+         entries.extend(fields(span, &variant.fields));
+         if let Some((_, expr)) = &variant.discriminant {
+            entries.push(("discriminant", stream(expr)));
+         }
+         map(entries)
+      }).collect()).into())),
+   }
+
+   map(entries)
+}
+
+
+// function signatures, the receiver is `none`, `value`, `ref`, `ref_mut` or `typed`
+fn fn_entries(span: Span, attrs_list: &[Attribute], vis: &Visibility, sig: &Signature) -> Vec<(&'static str, Rc<Item>)> {
+
+   let receiver = match sig.receiver() {
+      None => "none",
+      Some(receiver) if receiver.colon_token.is_some() => "typed",
+      Some(receiver) => match (&receiver.reference, &receiver.mutability) {
+         (Some(_), Some(_)) => "ref_mut", (Some(_), None) => "ref", (None, _) => "value",
+      },
+   };
+
+   let args = sig.inputs.iter().filter_map(|arg| match arg {
+      FnArg::Typed(arg) => Some(map([("pat", stream(&arg.pat)), ("ty", stream(&arg.ty)), ("attrs", attrs(&arg.attrs))])),
+      FnArg::Receiver(_) => None,
+   }).collect();
+
+   let mut entries = vec![
+      ("kind", ident("fn", span)),
+      ("name", Item::Ident(sig.ident.clone()).into()),
+      ("vis", stream(vis)),
+      ("attrs", attrs(attrs_list)),
+   ];
+
+   entries.extend(generics(&sig.generics));
+
+   entries.extend([
+      ("sig", stream(sig)),
+      ("receiver", ident(receiver, span)),
+      ("inputs", stream(&sig.inputs)),
+      ("args", Item::List(args).into()),
+      ("output", stream(&sig.output)),
+      ("asyncness", flag(sig.asyncness.is_some(), span)),
+      ("unsafety", flag(sig.unsafety.is_some(), span)),
+      ("constness", flag(sig.constness.is_some(), span)),
+   ]);
+
+   entries
+}
+
+fn trait_item(span: Span, item: ItemTrait) -> Rc<Item> {
+
+   let methods = item.items.iter().filter_map(|item| match item {
+      TraitItem::Fn(method) => {
+         let mut entries = fn_entries(span, &method.attrs, &Visibility::Inherited, &method.sig);
+         entries.push(("default", flag(method.default.is_some(), span)));
+         Some(map(entries))
+      },
+      _ => None,
+   }).collect();
+
+   let mut entries = vec![
+      ("kind", ident("trait", span)),
+      ("name", Item::Ident(item.ident.clone()).into()),
+      ("vis", stream(&item.vis)),
+      ("attrs", attrs(&item.attrs)),
+   ];
+
+   entries.extend(generics(&item.generics));
+
+   entries.extend([
+      ("unsafety", flag(item.unsafety.is_some(), span)),
+      ("supertraits", stream(&item.supertraits)),
+      ("methods", Item::List(methods).into()),
+   ]);
+
+   map(entries)
+}
+
+// the trait is only present for trait impls
+fn impl_item(span: Span, item: ItemImpl) -> Rc<Item> {
+
+   let methods = item.items.iter().filter_map(|item| match item {
+      ImplItem::Fn(method) => Some(map(fn_entries(span, &method.attrs, &method.vis, &method.sig))),
+      _ => None,
+   }).collect();
+
+   let mut entries = vec![
+      ("kind", ident("impl", span)),
+      ("attrs", attrs(&item.attrs)),
+   ];
+
+   entries.extend(generics(&item.generics));
+
+   entries.push(("unsafety", flag(item.unsafety.is_some(), span)));
+
+   if let Some((bang, path, _)) = &item.trait_ {
+      entries.push(("trait", Item::Stream(quote!{ #bang #path }).into()));
+   }
+
+   entries.extend([
+      ("self_ty", stream(&item.self_ty)),
+      ("methods", Item::List(methods).into()),
+   ]);
+
+   map(entries)
+}
+
+
+// `@parse_item{ struct Foo<T> { a: T } }`, a struct, enum, union, trait, impl or fn as a map
+pub fn parse_item(span: Span, args: TokenStream) -> Res<Rc<Item>> {
+   Ok(match parse2(args)? {
+      SynItem::Struct(item) => data_item(span, item.into()),
+      SynItem::Enum(item) => data_item(span, item.into()),
+      SynItem::Union(item) => data_item(span, item.into()),
+      SynItem::Trait(item) => trait_item(span, item),
+      SynItem::Impl(item) => impl_item(span, item),
+      SynItem::Fn(item) => map(fn_entries(span, &item.attrs, &item.vis, &item.sig)),
+      // signatures without a body
+      SynItem::Verbatim(tokens) => {
+         let item: ForeignItemFn = parse2(tokens)?;
+         map(fn_entries(span, &item.attrs, &item.vis, &item.sig))
+      },
+      other => err!(other.span(), "expected a struct, enum, union, trait, impl or fn"),
+   })
+}
+
+
+// generics stream, `<T> where T: Copy`, or the generics of a parsed item
+fn generics_arg(span: Span, item: &Item) -> Res<Generics> {
+
+   let tokens = match item {
+      Item::Stream(stream) => stream.clone(),
+      Item::Map(map) => match (map.get("generics"), map.get("where_clause")) {
+         (Some(generics), Some(where_clause)) => match (generics.as_ref(), where_clause.as_ref()) {
+            (Item::Stream(generics), Item::Stream(where_clause)) => generics.clone().into_iter().chain(where_clause.clone()).collect(),
+            _ => err!(span, "expected generics and where clause streams"),
+         },
+         _ => err!(span, "expected generics or a parsed item, found a map without generics"),
+      },
+      other => err!(span, "expected generics or a parsed item, found a {} item", other.kind()),
+   };
+
+   let parser = |input: ParseStream| {
+      let mut generics: Generics = input.parse()?;
+      generics.where_clause = input.parse()?;
+      Ok(generics)
+   };
+
+   Ok(parser.parse2(tokens)?)
+}
+
+fn generics_item(generics: &Generics) -> Rc<Item> {
+   let where_clause = &generics.where_clause;
+   Item::Stream(quote!{ #generics #where_clause }).into()
+}
+
+// remaining arguments, rejoined as they may contain commas between angle brackets
+fn rest_args(args: impl Iterator<Item=TokenStream>) -> TokenStream {
+   let mut tokens = TokenStream::new();
+   for (i, arg) in args.enumerate() {
+      if i > 0 { tokens.extend(quote!{,}) }
+      tokens.extend(arg);
+   }
+   tokens
+}
+
+
+// generics functions mirroring `syn::Generics::split_for_impl`
+pub fn call_generics(span: Span, name: &str, args: Vec<TokenStream>, env: &mut Env) -> Res<Option<Rc<Item>>> {
+
+   match name {
+      "split_for_impl" | "impl_generics" | "ty_generics" | "where_clause" => expect_args(span, name, &args, 1, 1)?,
+      "add_type_param" | "add_lifetime" | "add_bound" => expect_args(span, name, &args, 2, usize::MAX)?,
+      _ => return Ok(None),
+   }
+
+   let mut args = args.into_iter();
+   let (item_span, item) = arg_item(span, args.next().unwrap(), env)?; // checked
+   let mut generics = generics_arg(item_span, &item)?;
+
+   Ok(Some(match name {
+
+      "split_for_impl" => {
+         let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+         map([("impl_generics", stream(impl_generics)), ("ty_generics", stream(ty_generics)), ("where_clause", stream(where_clause))])
+      },
+
+      "impl_generics" => stream(generics.split_for_impl().0),
+      "ty_generics" => stream(generics.split_for_impl().1),
+      "where_clause" => stream(generics.split_for_impl().2),
+
+      "add_type_param" => {
+         let params = Punctuated::<TypeParam, Comma>::parse_terminated.parse2(rest_args(args))?;
+         generics.params.extend(params.into_iter().map(GenericParam::Type));
+         generics_item(&generics)
+      },
+
+      // lifetimes go before other parameters
+      "add_lifetime" => {
+         let params = Punctuated::<LifetimeParam, Comma>::parse_terminated.parse2(rest_args(args))?;
+         let at = generics.lifetimes().count();
+         for (i, param) in params.into_iter().enumerate() {
+            generics.params.insert(at + i, GenericParam::Lifetime(param));
+         }
+         generics_item(&generics)
+      },
+
+      "add_bound" => {
+         let predicates = Punctuated::<WherePredicate, Comma>::parse_terminated.parse2(rest_args(args))?;
+         generics.make_where_clause().predicates.extend(predicates);
+         generics_item(&generics)
+      },
+
+      _ => unreachable!(),
+   }))
+}
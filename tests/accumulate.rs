@@ -0,0 +1,23 @@
+use template_macro::template;
+
+template!{
+   $names: ()
+   $table: {}
+   $count: 0
+   $[op in (add, sub, mul)]{
+      pub fn $#{op_ $(op)}() -> &'static str { $str{$(op)} }
+      $names +: $#{op_ $(op)}
+      $table +: { $(op): $=(@index + 1) }
+      $outer count: $=(count + 1)
+   }
+   pub const DISPATCH: [fn() -> &'static str; $len(names)] = [$[names]{ $(@value) } sep {,}];
+   pub const TABLE: [u8; 3] = [$[table]{ $(@value) } sep {,}];
+   pub const COUNT: u8 = $(count);
+}
+
+#[test]
+fn accumulate() {
+   assert_eq!(DISPATCH.map(|f| f()), ["add", "sub", "mul"]);
+   assert_eq!(TABLE, [1, 2, 3]);
+   assert_eq!(COUNT, 3);
+}
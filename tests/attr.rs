@@ -0,0 +1,33 @@
+use template_macro::template_attr;
+
+#[template_attr(
+   $(item_tokens)
+   $prefix: get_
+   impl $(item.name) {
+      $[f in item.fields]{
+         pub fn $#{$(prefix) $(f.name)}(&self) -> &$(f.ty) { &self.$(f.name) }
+      }
+   }
+   pub const SOURCE: &str = $stringify{$(item_tokens)};
+)]
+#[derive(Debug, Default)]
+pub struct Config { name: &'static str, level: u8 }
+
+#[template_attr(pub const CONST_NAME: &str = $str{$(item.name ?? none)};)]
+pub const X: u8 = 1;
+
+// only the output remains
+#[template_attr(pub const REPLACED: &str = $str{$(item.name)};)]
+pub struct Removed;
+
+pub struct Removed(pub u8);
+
+#[test]
+fn attr() {
+   let c = Config { name: "a", level: 2 };
+   assert_eq!(*c.get_name(), "a");
+   assert_eq!(*c.get_level(), 2);
+   assert!(SOURCE.contains("struct Config"));
+   assert_eq!(CONST_NAME, "none");
+   assert_eq!(Removed(REPLACED.len() as u8).0, 7);
+}
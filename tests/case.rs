@@ -0,0 +1,38 @@
+use template_macro::template;
+
+template!{
+   $names: (FooBar, HTTPServer, snake_case_name, u8Value)
+   $[names]{
+      pub const $upper_snake(@value): &str = $kebab(@value);
+      pub fn $snake(@value)() -> &'static str { stringify!($camel(@value)) }
+   }
+   pub struct $pascal{ my_ $(names.2) }(u8);
+   $s: "some-text"
+   pub const $upper_snake(s): &str = "x";
+   pub const $upper_snake{$#{a b} _c}: u8 = 1;
+
+   // raw identifiers and separate tokens
+   pub struct $pascal{ foo bar }(u8);
+   pub const $upper_snake{ r#type }: &str = $str{$snake{ r#Struct }};
+   pub fn $snake{ r#Type }() -> &'static str { $kebab{ r#type value } }
+   $kw: {{ Loop }}
+   pub const LOWER_KW: &str = stringify!($snake(kw));
+}
+
+#[test]
+fn case() {
+   assert_eq!(FOO_BAR, "foo-bar");
+   assert_eq!(HTTP_SERVER, "http-server");
+   assert_eq!(SNAKE_CASE_NAME, "snake-case-name");
+   assert_eq!(U8_VALUE, "u8-value");
+   assert_eq!(foo_bar(), "fooBar");
+   assert_eq!(http_server(), "httpServer");
+   assert_eq!(u8_value(), "u8Value");
+   assert_eq!(MySnakeCaseName(1).0, 1);
+   assert_eq!(SOME_TEXT, "x");
+   assert_eq!(AB_C, 1);
+   assert_eq!(FooBar(2).0, 2);
+   assert_eq!(TYPE, "struct");
+   assert_eq!(r#type(), "type-value");
+   assert_eq!(LOWER_KW, "r#loop");
+}
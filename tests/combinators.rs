@@ -0,0 +1,34 @@
+use template_macro::template;
+
+template!{
+   $names: (a, b, c)
+   $types: (u8, u16, u32, u64)
+   pub struct Z { $[(n, t) in @zip(names, types)]{ pub $(n): $(t), } }
+   pub const ZIP_LEN: usize = $len(@zip(names, types));
+   pub const CHAIN: [&str; 5] = [$[x in @chain(names, (d, e))]{ $str{$(x)}, }];
+   pub const PRODUCT: [&str; 6] = [$[(x, y) in @product(@range(0..2), names)]{ $str{$(x)$(y)}, }];
+   pub const REV: [u8; 3] = [$[x in @reverse(@range(1..=3u8))]{ $(x), }];
+   pub const ENUM: [usize; 3] = [$[(i, _) in @enumerate(names)]{ $=(i * 2), }];
+   $two: 2
+   pub const SKIP_TAKE: [u8; 2] = [$[x in @take(@skip(@range(0..10u8), 3), two)]{ $(x), }];
+   pub const STEP: [u8; 4] = [$[x in @step_by(@range(0..8u8), 2)]{ $(x) $!last{,} }];
+   pub const WIN: [u8; 2] = [$[(a, b) in @windows(@range(1..4u8), 2)]{ $=(a * b), }];
+   pub const CHUNKS: [usize; 2] = [$[c in @chunks(types, 3)]{ $len(c), }];
+   pub const LAST: &str = $[x in @zip(names, types)]{ $last{ $str{$(x.0)} } };
+}
+
+#[test]
+fn combinators() {
+   let z = Z { a: 1, b: 2, c: 3 };
+   assert_eq!(z.a as u32 + z.b as u32 + z.c, 6);
+   assert_eq!(ZIP_LEN, 3);
+   assert_eq!(CHAIN, ["a", "b", "c", "d", "e"]);
+   assert_eq!(PRODUCT, ["0a", "0b", "0c", "1a", "1b", "1c"]);
+   assert_eq!(REV, [3, 2, 1]);
+   assert_eq!(ENUM, [0, 2, 4]);
+   assert_eq!(SKIP_TAKE, [3, 4]);
+   assert_eq!(STEP, [0, 2, 4, 6]);
+   assert_eq!(WIN, [2, 6]);
+   assert_eq!(CHUNKS, [3, 1]);
+   assert_eq!(LAST, "c");
+}
@@ -0,0 +1,31 @@
+use template_macro::template;
+
+template!{
+   $fields: { a: { ty: u8, optional: true }, b: { ty: u16, optional: false }, c: { ty: u32 } }
+   $empty_list: ()
+   $[fields]{
+      pub const $upper_snake{is_ $(@key)}: u8 =
+         $if (@has(@value.optional) && @value.optional == true) { 1 }
+         $else if (@value.ty == {u16}) { 2 }
+         $else if (!@has(@value.optional)) { 3 }
+         $else { 4 };
+   }
+   pub const EMPTY: bool = $if (empty(empty_list)) { true } $else { false };
+   pub const NOT_EMPTY: bool = $if (!empty(fields) || fields.a.ty != {u8}) { true } $else { false };
+   pub const LIT: u8 = $if (fields.a.optional) { 7 } $if ((1 == 2)) { 8 };
+
+   // identifiers are compared in braces, bare ones are item names
+   $[t in (u8, i16, u32)]{
+      pub const $upper_snake{signed_ $(t)}: bool = $if (t == {i16} || t == {i32}) { true } $else { false };
+   }
+}
+
+#[test]
+fn cond() {
+   assert_eq!(IS_A, 1);
+   assert_eq!(IS_B, 2);
+   assert_eq!(IS_C, 3);
+   assert_eq!([EMPTY, NOT_EMPTY], [true, true]);
+   assert_eq!(LIT, 7);
+   assert_eq!([SIGNED_U8, SIGNED_I16, SIGNED_U32], [false, true, false]);
+}
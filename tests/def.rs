@@ -0,0 +1,45 @@
+use template_macro::template;
+
+template!{
+   $def getter(name, ty) {
+      pub fn $#{get_ $(name)}(&self) -> $(ty) { self.$(name) }
+   }
+   $def count(n) {
+      $if (n > 0) { 1 + $count!($=(n - 1)) } $else { 1 }
+   }
+   pub struct S { a: u8, b: u16 }
+   impl S {
+      $getter!(a, u8)
+      $getter!(b, u16)
+   }
+   $fields: ((x, i32), (y, i64))
+   pub struct P { $[fields]{ $(@value.0): $(@value.1), } }
+   $def field_getter(pair) { $getter!($(pair.0), $(pair.1)) }
+   impl P { $[fields]{ $field_getter!(@(@value)) } }
+   pub const COUNT: u8 = $count!(4);
+}
+
+// bodies see the bindings where they are defined, not the caller's
+template!{
+   $def outer_x() { $(x ?? 0) }
+   $[x in (1, 2)]{
+      $def inner_x() { $(x) }
+      $def down(n) { $if (n > 0) { $(n) + $down!($=(n - 1)) } $else { $inner_x!() } }
+      pub const $#{CALLER_ $(x)}: u8 = $outer_x!();
+      pub const $#{DEFINED_ $(x)}: u8 = $inner_x!();
+      pub const $#{DOWN_ $(x)}: u8 = $down!(3);
+   }
+}
+
+#[test]
+fn def() {
+   let s = S { a: 1, b: 2 };
+   assert_eq!(s.get_a(), 1);
+   assert_eq!(s.get_b(), 2);
+   let p = P { x: 3, y: 4 };
+   assert_eq!(p.get_x() as i64 + p.get_y(), 7);
+   assert_eq!(COUNT, 5);
+   assert_eq!([CALLER_1, CALLER_2], [0, 0]);
+   assert_eq!([DEFINED_1, DEFINED_2], [1, 2]);
+   assert_eq!([DOWN_1, DOWN_2], [7, 8]);
+}
@@ -0,0 +1,45 @@
+use template_macro::template;
+
+template!{
+   $none: ()
+   $nomap: {}
+   $some: (1, 2, 3)
+   pub const A: [u8; 0] = [$[none]{ $(@value) }];
+   pub const B: [u8; 0] = [$[nomap]{ $(@value) } sep {,}];
+   pub const C: u8 = $[none]{ $(@value) } sep {+} $else { 7 };
+   pub const D: u8 = $[x in some where x > 5]{ $(x) } $empty { 8 };
+   pub const E: u8 = $[some]{ $(@value) } sep {+} $empty { 0 };
+   pub const F: u8 = $sorted[nomap]{ 1 } $empty { 9 };
+   pub const G: u8 = $[x in missing?]{ $(x) } $else { 5 };
+   $l: ()
+   pub const H: u8 = 1 $[l]{ + $(@value) };
+}
+
+template!{
+   $c: (false, true)
+   pub fn chain(value: bool) -> u8 {
+      if value { 1 } $[c]{ else if $(@value) { $=(@index + 10) } } else { 3 }
+   }
+   pub fn fallback() -> u8 {
+      $l: ()
+      if false { 1 } $[l]{ else if $(@value) { 2 } } else { 3 }
+   }
+   pub fn both(value: bool) -> u8 {
+      $l: ()
+      if value { 1 } $[l]{ else if $(@value) { 2 } } $else { else if !value { 4 } } else { 3 }
+   }
+}
+
+#[test]
+fn empty() {
+   assert_eq!(A.len() + B.len(), 0);
+   assert_eq!(C, 7);
+   assert_eq!(D, 8);
+   assert_eq!(E, 6);
+   assert_eq!(F, 9);
+   assert_eq!(G, 5);
+   assert_eq!(H, 1);
+   assert_eq!(chain(true) + chain(false), 12);
+   assert_eq!(fallback(), 3);
+   assert_eq!(both(false), 4);
+}
@@ -0,0 +1,21 @@
+use template_macro::template;
+
+template!{
+   $fields: {
+      a: { ty: u8, optional: true },
+      b: { ty: u16, optional: false },
+      c: { ty: u32, optional: true },
+   }
+   pub const OPTIONAL: [&str; 2] = [$[(name, f) in fields where f.optional == true]{ $str{$(name)} $!last{,} }];
+   pub const IDX: [usize; 2] = [$[(_, f) in fields where f.optional]{ $(@index), }];
+   pub const FIRST: &str = $[(name, f) in fields where !f.optional || name == {c}]{ $first{ $str{$(name)} } };
+   pub const EVEN: [u8; 3] = [$[@range(0..6u8) where @value % 2 == 0]{ $(@value), }];
+}
+
+#[test]
+fn filter() {
+   assert_eq!(OPTIONAL, ["a", "c"]);
+   assert_eq!(IDX, [0, 1]);
+   assert_eq!(FIRST, "b");
+   assert_eq!(EVEN, [0, 2, 4]);
+}
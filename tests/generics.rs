@@ -0,0 +1,36 @@
+use template_macro::template;
+
+pub trait Describe { fn describe(&self) -> usize; }
+pub trait Holds<'h> { fn held(&'h self) -> &'h str; }
+
+template!{
+   $s: @parse_item{ pub struct Wrapper<'a, T: Clone, const N: usize> where T: Default { items: [&'a T; N], name: &'a str } }
+   $(s.vis) struct $(s.name) $(s.generics) $(s.where_clause) {
+      $[f in s.fields]{ $(f.name): $(f.ty), }
+   }
+
+   $split: @split_for_impl(s)
+   impl $(split.impl_generics) Describe for $(s.name) $(split.ty_generics) $(split.where_clause) {
+      fn describe(&self) -> usize { N }
+   }
+
+   $more: @add_bound(@add_lifetime(s, 'h), 'a: 'h, T: std::fmt::Debug)
+   impl $(@impl_generics(more)) Holds<'h> for $(s.name) $(@ty_generics(s)) $(@where_clause(more)) {
+      fn held(&'h self) -> &'h str { self.name }
+   }
+
+   $extra: @add_type_param({{<X>}}, U: Into<std::collections::HashMap<u8, u8>>, V)
+   pub const EXTRA: &str = $stringify{$(extra)};
+   pub const SPLIT: &str = $stringify{$(@split_for_impl(extra).ty_generics)};
+}
+
+#[test]
+fn generics() {
+   let v = 1u8;
+   let w = Wrapper::<u8, 2> { items: [&v, &v], name: "w" };
+   assert_eq!(w.describe(), 2);
+   assert_eq!(w.held(), "w");
+   assert_eq!(w.items.len(), 2);
+   assert_eq!(EXTRA, "<X, U: Into<std::collections::HashMap<u8, u8>>, V>");
+   assert_eq!(SPLIT, "<X, U, V>");
+}
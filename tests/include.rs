@@ -0,0 +1,62 @@
+use template_macro::template;
+
+template!{
+   $data: @include_json("tests/ops.json")
+   #[derive(Debug, PartialEq)]
+   pub enum Op { $[data.ops]{ $pascal(@value.name) = $(@value.code), } }
+   $[data.ops]{ #[doc = $(@value.doc)] pub const $upper_snake(@value.name): &str = $(@value.doc); }
+   pub const REGS: [&str; $len(data.regs)] = [$[data.regs]{ $(@value), }];
+   pub const META: [&str; 3] = [$[data.meta]{ $str{$(@key)}, }];
+}
+
+// only fns and consts, which could be impl items as well
+template!{
+   $data: @include_json("tests/ops.json")
+   $[data.ops]{ pub fn $#{$(@value.name)}() -> u8 { $(@value.code) } }
+   pub const VERSION: u8 = $(data.meta.version);
+}
+
+pub struct Regs;
+
+impl Regs {
+   template!{
+      $data: @include_json("tests/ops.json")
+      $[data.regs]{ pub fn $#{$(@value)}() -> &'static str { $(@value) } }
+   }
+}
+
+pub trait Code { fn code(&self) -> u8; }
+
+impl Code for Op {
+   template!{
+      $data: @include_json("tests/ops.json")
+      fn code(&self) -> u8 { match self { $[data.ops]{ Op::$pascal(@value.name) => $(@value.code), } } }
+   }
+}
+
+#[test]
+fn include() {
+   assert_eq!(Op::Sub as u8, 2);
+   assert_eq!(ADD, "adds two values");
+   assert_eq!(add() + sub(), 3);
+   assert_eq!(REGS, ["r0", "r1", "sp"]);
+   assert_eq!(VERSION, 3);
+   assert_eq!(META, ["version", "big", "not-ident"]);
+}
+
+#[test]
+fn include_positions() {
+   assert_eq!(Regs::sp(), "sp");
+   assert_eq!(Op::Add.code(), 1);
+   let big: bool = template!{
+      $data: @include_json("tests/ops.json")
+      $(data.meta.big)
+   };
+   template!{
+      $data: @include_json("tests/ops.json")
+      let code = $(data.ops.1.code);
+      let doc = $(data.ops.1.doc);
+   }
+   assert!(big);
+   assert_eq!((code, doc), (2, "subtracts"));
+}
@@ -0,0 +1,43 @@
+use template_macro::template_lib;
+
+template_lib!{
+   numeric {
+      $ints: (u8, u16, u32)
+      $def zero(ty) { pub const $upper_snake{ZERO_ $(ty)}: $(ty) = 0; }
+      ignored tokens
+   }
+}
+
+numeric!{
+   $[ints]{ $zero!(@(@value)) }
+   pub const COUNT: usize = $len(ints);
+}
+
+mod inner {
+   template_macro::template_lib!{
+      #[macro_export]
+      fields { $names: (a, b) }
+   }
+}
+
+fields!{ pub struct F { $[names]{ pub $(@value): u8, } } }
+
+// escaped `$` reach the output through the `macro_rules!` shim
+template_lib!{
+   macros {
+      $def scaling(name, factor) {
+         macro_rules! $(name) { ($$x:expr) => { $$x * $(factor) } }
+      }
+   }
+}
+
+macros!{ $scaling!(double, 2) }
+
+#[test]
+fn library() {
+   assert_eq!(ZERO_U8 + ZERO_U16 as u8 + ZERO_U32 as u8, 0);
+   assert_eq!(COUNT, 3);
+   let f = F { a: 1, b: 2 };
+   assert_eq!(f.a + f.b, 3);
+   assert_eq!(double!(21), 42);
+}
@@ -0,0 +1,40 @@
+use template_macro::template;
+
+template!{
+   $fields: (
+      { name: a, ty: u8, doc: "first" },
+      { name: b, ty: u16, vis: pub },
+   )
+   pub struct S {
+      $[field in fields]{
+         $if (@has(field.doc)) { #[doc = $(field.doc)] }
+         $(field.vis ?? pub) $(field.name): $(field.ty),
+      }
+   }
+   pub const DOCS: [&str; 2] = [$[field in fields]{ $str{$(field.doc?)} } sep {,}];
+   pub const HAS: [bool; 2] = [$[field in fields]{ $(@has(field.vis)) } sep {,}];
+   pub const N: u8 = $=(missing ?? 3);
+   pub const M: u8 = $(fields.5.ty ?? @(fields.0.ty)) ::MAX;
+   pub const LEN: [usize; 2] = [$len(fields.0.missing?), $len(fields.0.doc?)];
+   pub const ITER: usize = 0 $[fields.0.missing?]{ + 1 } $[@reverse(fields.1.doc?)]{ + 1 };
+   pub const EXPR: [bool; 3] = [$=(@has(fields.0.doc)), $=(!@has(fields.1.doc) && len(fields.1.doc?) == 0), $=(empty(fields.2?))];
+   pub const CALL: usize = $=(@range(0..4).3 + len(@range(0..2)));
+   // an assigned empty stream is still one item
+   $none: {{}}
+   pub const STREAM: [usize; 2] = [$len(none), $[none]{ 1 } $else { 0 }];
+}
+
+#[test]
+fn optional() {
+   let s = S { a: 1, b: 2 };
+   assert_eq!(s.a + s.b as u8, 3);
+   assert_eq!(DOCS, ["first", ""]);
+   assert_eq!(HAS, [false, true]);
+   assert_eq!(N, 3);
+   assert_eq!(M, 255);
+   assert_eq!(LEN, [0, 1]);
+   assert_eq!(ITER, 0);
+   assert_eq!(EXPR, [true, true, true]);
+   assert_eq!(CALL, 5);
+   assert_eq!(STREAM, [1, 1]);
+}
@@ -0,0 +1,16 @@
+use template_macro::template;
+
+template!{
+   $rows: (a, b, c)
+   $cols: (1, 2)
+   pub const TABLE: [[usize; 2]; 3] = [ $[rows]{ [ $[cols]{ $=(@outer.index * 10 + @index), } ], } ];
+   pub const NAMES: [&str; 6] = [ $[rows]{ $[cols]{ $str{$(@1.value) $(@value)}, } } ];
+   pub const DEEP: [usize; 4] = [ $[@range(0..2)]{ $[@range(0..1)]{ $[@range(0..2)]{ $=(@outer.outer.value * 2 + @2.index * 0 + @value), } } } ];
+}
+
+#[test]
+fn outer() {
+   assert_eq!(TABLE, [[0, 1], [10, 11], [20, 21]]);
+   assert_eq!(NAMES, ["a1", "a2", "b1", "b2", "c1", "c2"]);
+   assert_eq!(DEEP, [0, 1, 2, 3]);
+}
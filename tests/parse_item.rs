@@ -0,0 +1,35 @@
+use template_macro::template;
+
+template!{
+   $s: @parse_item{
+      /// a point
+      #[derive(Debug, Clone)]
+      pub struct Point<T: Copy> where T: Default { pub x: T, y: T }
+   }
+   $(s.attrs.1)
+   $(s.vis) struct $(s.name) $(s.generics) $(s.where_clause) {
+      $[f in s.fields]{ $(f.vis) $(f.name): $(f.ty), }
+   }
+   pub const FIELDS: [&str; $len(s.fields)] = [$[f in s.fields]{ $str{$(f.name)} } sep {,}];
+   pub const KIND: &str = $str{$(s.kind) $(s.style)};
+
+   $e: @parse_item{ enum Op { Add = 1, Neg(i32), Mul { a: u8, b: u8 }, Nop } }
+   pub const VARIANTS: [(&str, &str, &str); 4] = [
+      $[v in e.variants]{ ($str{$(v.name)}, $str{$(v.style)}, $stringify{$(v.discriminant ?? {{none}})}) } sep {,}
+   ];
+   $t: @parse_item(struct Pair(u8, pub u16);)
+   pub const TUPLE: [&str; 2] = [$[f in t.fields]{ $str{$(f.name) $(f.ty)} } sep {,}];
+   $u: @parse_item{ union Bits { int: u32, float: f32 } }
+   pub const UNION: &str = $str{$(u.kind) $(u.style) $[f in u.fields]{ ' ' $(f.name) }};
+}
+
+#[test]
+fn parse_item() {
+   let p = Point::<u8> { x: 1, y: 2 };
+   assert_eq!(p.clone().x + p.y, 3);
+   assert_eq!(FIELDS, ["x", "y"]);
+   assert_eq!(KIND, "structnamed");
+   assert_eq!(VARIANTS, [("Add", "unit", "1"), ("Neg", "unnamed", "none"), ("Mul", "named", "none"), ("Nop", "unit", "none")]);
+   assert_eq!(TUPLE, ["0u8", "1u16"]);
+   assert_eq!(UNION, "unionnamed int float");
+}
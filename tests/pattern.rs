@@ -0,0 +1,25 @@
+use template_macro::template;
+
+template!{
+   $fields: ((x, i32), (y, i64), (z, u8))
+   pub struct P { $[(name, ty) in fields]{ pub $(name): $(ty), } }
+   $types: { small: u8, large: u64 }
+   $[(k, v) in types]{ pub type $pascal(k) = $(v); }
+   $[t in types]{ pub const $#{MAX_ $upper_snake(t)}: $(t) = $(t)::MAX; }
+   $nested: ((a, (1, 2)), (b, (3, 4)))
+   pub const SUMS: [u8; 2] = [$[(_, (l, r)) in nested]{ $=(l + r), }];
+   pub const IDX: [usize; 3] = [$[f in fields]{ $(@index), }];
+   $[row in @range(0..2)]{ $[col in @range(0..2)]{ pub const $#{C $(row) $(col)}: u8 = $=(row * 2 + col); } }
+}
+
+#[test]
+fn pattern() {
+   let p = P { x: 1, y: 2, z: 3 };
+   assert_eq!(p.x as i64 + p.y + p.z as i64, 6);
+   let _: Small = 1u8;
+   let _: Large = 1u64;
+   assert_eq!(MAX_U8, 255);
+   assert_eq!(SUMS, [3, 7]);
+   assert_eq!(IDX, [0, 1, 2]);
+   assert_eq!(C11, 3);
+}
@@ -0,0 +1,28 @@
+use template_macro::template;
+
+template!{
+   $n: @range(0..4)
+   pub const A: [usize; $len(n)] = [$[n]{ $(@value), }];
+   pub const B: [i32; $len(@range(-2..=2))] = [$[@range(-2..=2)]{ $(@value), }];
+   pub const C: [u8; 3] = [$[@range(10u8..0, -4)]{ $(@value), }];
+   pub const D: usize = $(n.3);
+   pub const E: usize = $(@range(0..100, 10).5);
+   $m: { r: @range(1..3) }
+   pub const F: [u32; 2] = [$[m.r]{ $(@value), }];
+   pub const G: usize = $len(@range(5..0));
+   pub const H: [u8; 6] = [$[@range(250u8..256)]{ $(@value), }];
+   pub const I: [i8; 2] = [$[@range(126i8..=127)]{ $(@value), }];
+}
+
+#[test]
+fn range() {
+   assert_eq!(A, [0, 1, 2, 3]);
+   assert_eq!(B, [-2, -1, 0, 1, 2]);
+   assert_eq!(C, [10, 6, 2]);
+   assert_eq!(D, 3);
+   assert_eq!(E, 50);
+   assert_eq!(F, [1, 2]);
+   assert_eq!(G, 0);
+   assert_eq!(H, [250, 251, 252, 253, 254, 255]);
+   assert_eq!(I, [126, 127]);
+}
@@ -0,0 +1,69 @@
+use template_macro::template;
+
+template!{
+   $count: 0
+   $last: none
+   $[op in (a, b, c)]{
+      $set count: $=(count + 1)
+      $let last: $(op)
+      $global seen: $(op)
+   }
+   pub const COUNT: u8 = $(count);
+   pub const LAST: &str = $str{$(last)};
+   pub const SEEN: &str = $str{$(seen)};
+}
+
+pub struct Values;
+
+impl Values {
+   template!{
+      $x: 1
+      $[@range(0..2)]{ $let x: $(@value) pub const fn $#{value_ $(@value)}() -> u8 { $(x) } }
+   }
+}
+
+template!{
+   $x: 1
+   $[@range(0..1)]{ $global x: 3 $let x: 2 pub const INNER: u8 = $(x); }
+   pub const OUTER: u8 = $(x);
+}
+
+// shadowing without `$let` warns, but keeps the output valid in any position
+#[allow(deprecated)]
+mod shadowed {
+   use template_macro::template;
+
+   template!{
+      $x: 1
+      $[@range(0..1)]{ $x: 2 pub const INNER: u8 = $(x); }
+   }
+
+   pub struct S;
+
+   impl S {
+      template!{
+         $x: 1
+         $[@range(0..1)]{ $x: 2 pub fn inner() -> u8 { $(x) } }
+      }
+   }
+
+   pub fn expr() -> u8 {
+      template!{ $x: 1 $[@range(0..1)]{ $x: 2 $(x) } }
+   }
+}
+
+#[test]
+fn scoping() {
+   assert_eq!(COUNT, 3);
+   assert_eq!(LAST, "none");
+   assert_eq!(SEEN, "c");
+   assert_eq!(Values::value_0() + Values::value_1(), 1);
+   assert_eq!(INNER + OUTER, 5);
+   assert_eq!(shadowed::INNER + shadowed::S::inner() + shadowed::expr(), 6);
+   let sum = template!{
+      $x: 1
+      $[@range(1..3)]{ $let x: $(@value) $(x) + }
+      $(x)
+   };
+   assert_eq!(sum, 4);
+}
@@ -0,0 +1,22 @@
+use template_macro::template;
+
+template!{
+   $names: (a, b, c)
+   pub const LIST: [&str; 3] = [$[n in names]{ $str{$(n)} } sep {,}];
+   pub const TRAIL: [&str; 3] = [$[n in names]{ $str{$(n)} } sep trailing {,}];
+   $plus: {{+}}
+   pub const SUM: u8 = $[@range(1..=4u8)]{ $(@value) } sep { $(plus) };
+   $zy: { z: 1, y: 2 }
+   pub const SORTED: [&str; 2] = [$sorted[zy]{ $str{$(@key)} } sep {,}];
+   pub const fn sep() -> u8 { 7 }
+   pub const PLAIN: u8 = $[@range(1..2u8)]{ $(@value) } + sep();
+}
+
+#[test]
+fn separator() {
+   assert_eq!(LIST, ["a", "b", "c"]);
+   assert_eq!(TRAIL, ["a", "b", "c"]);
+   assert_eq!(SUM, 10);
+   assert_eq!(SORTED, ["y", "z"]);
+   assert_eq!(PLAIN, 8);
+}
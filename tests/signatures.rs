@@ -0,0 +1,56 @@
+use template_macro::template;
+
+template!{
+   $tr: @parse_item{
+      pub trait Shape: Clone {
+         fn area(&self) -> u32;
+         fn scale(&mut self, by: u32, _extra: bool);
+         fn name() -> &'static str { "shape" }
+         unsafe fn raw(self) {}
+      }
+   }
+   #[allow(clippy::missing_safety_doc)]
+   pub trait Shape: Clone {
+      $[m in tr.methods]{ $(m.sig); }
+   }
+   #[derive(Clone)] pub struct Sq(u32);
+   #[derive(Clone)] pub struct Wrap(Sq);
+   impl Shape for Sq {
+      fn area(&self) -> u32 { self.0 * self.0 }
+      fn scale(&mut self, by: u32, _extra: bool) { self.0 *= by }
+      fn name() -> &'static str { "sq" }
+      unsafe fn raw(self) {}
+   }
+   // forwarding
+   impl Shape for Wrap {
+      $[m in tr.methods]{
+         $(m.sig) {
+            $if (m.receiver == {none}) { <Sq as Shape>::$(m.name)($[a in m.args]{ $(a.pat) } sep {,}) }
+            $else if (m.receiver == {value}) { unsafe { self.0.$(m.name)($[a in m.args]{ $(a.pat) } sep {,}) } }
+            $else { self.0.$(m.name)($[a in m.args]{ $(a.pat) } sep {,}) }
+         }
+      }
+   }
+   pub const RECEIVERS: [&str; 4] = [$[m in tr.methods]{ $str{$(m.receiver)} } sep {,}];
+   pub const DEFAULTS: [bool; 4] = [$[m in tr.methods]{ $(m.default) } sep {,}];
+   pub const UNSAFE: [bool; 4] = [$[m in tr.methods]{ $(m.unsafety) } sep {,}];
+
+   $im: @parse_item{ impl<T> From<T> for Wrap where T: Into<u32> { fn from(t: T) -> Self { todo!() } } }
+   pub const IMPL: (&str, &str, usize) = ($stringify{$(im.trait)}, $stringify{$(im.self_ty)}, $len(im.methods));
+
+   $f: @parse_item{ pub async fn fetch<'a>(url: &'a str, (a, b): (u8, u8)) -> Option<u8>; }
+   pub const FN: (&str, &str, bool, usize) = ($str{$(f.name)}, $stringify{$(f.output)}, $(f.asyncness), $len(f.args));
+}
+
+#[test]
+fn signatures() {
+   let mut w = Wrap(Sq(2));
+   w.scale(3, false);
+   assert_eq!(w.area(), 36);
+   assert_eq!(Wrap::name(), "sq");
+   assert_eq!(RECEIVERS, ["ref", "ref_mut", "none", "value"]);
+   assert_eq!(DEFAULTS, [false, false, true, true]);
+   assert_eq!(UNSAFE, [false, false, false, true]);
+   assert_eq!(IMPL, ("From<T>", "Wrap", 1));
+   assert_eq!((FN.0, FN.2, FN.3), ("fetch", true, 2));
+}
@@ -0,0 +1,35 @@
+use template_macro::template;
+
+template!{
+   $list: (a, b, c, d)
+   $matrix: ((1, 2, 3), (4, 5, 6))
+   $nums: @range(0..10, 2)
+   pub const LAST: &str = $str{$(list.-1)};
+   pub const SECOND_LAST: &str = $str{$(list.-2)};
+   pub const MID: [&str; 2] = [$[list.1..3]{ $str{$(@value)} } sep {,}];
+   pub const HEAD: [&str; 2] = [$[list..2]{ $str{$(@value)} } sep {,}];
+   pub const TAIL: [&str; 3] = [$[list.1..]{ $str{$(@value)} } sep {,}];
+   pub const INIT: [&str; 3] = [$[list..-1]{ $str{$(@value)} } sep {,}];
+   pub const INCL: [&str; 4] = [$[list..=-1]{ $str{$(@value)} } sep {,}];
+   pub const CLAMP: usize = $len(list.2..10);
+   pub const FLOAT: u8 = $(matrix.1.2);
+   pub const ROW: [u8; 2] = [$[matrix.0.1..]{ $(@value) } sep {,}];
+   pub const RANGE: [u8; 2] = [$[nums.-2..]{ $(@value) } sep {,}];
+   pub const EXPR: u8 = $=(matrix.1.-1 + len(list.1..));
+}
+
+#[test]
+fn slice() {
+   assert_eq!(LAST, "d");
+   assert_eq!(SECOND_LAST, "c");
+   assert_eq!(MID, ["b", "c"]);
+   assert_eq!(HEAD, ["a", "b"]);
+   assert_eq!(TAIL, ["b", "c", "d"]);
+   assert_eq!(INIT, ["a", "b", "c"]);
+   assert_eq!(INCL, ["a", "b", "c", "d"]);
+   assert_eq!(CLAMP, 2);
+   assert_eq!(FLOAT, 6);
+   assert_eq!(ROW, [2, 3]);
+   assert_eq!(RANGE, [6, 8]);
+   assert_eq!(EXPR, 9);
+}
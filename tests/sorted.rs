@@ -0,0 +1,21 @@
+use template_macro::template;
+
+template!{
+   $map: { b: 2, c: 3, a: 1 }
+   pub const ORDER: [&str; 3] = [$[map]{ $str{$(@key)} } sep {,}];
+   pub const SORTED_KEYS: [&str; 3] = [$sorted[map]{ $str{$(@key)} } sep {,}];
+   pub const FIRST: u8 = $sorted[map]{ $first{ $(@value) } };
+   pub const NUMS: [u8; 4] = [$sorted[(10, 2, 33, 1)]{ $(@value) } sep {,}];
+   pub const INDICES: [&str; 3] = [$sorted[(b, c, a)]{ $str{$(@key) $(@value)} } sep {,}];
+   pub const RANGE: [i8; 3] = [$sorted[@range(2..-1, -1)]{ $(@value) } sep {,}];
+}
+
+#[test]
+fn sorted() {
+   assert_eq!(ORDER, ["b", "c", "a"]);
+   assert_eq!(SORTED_KEYS, ["a", "b", "c"]);
+   assert_eq!(FIRST, 1);
+   assert_eq!(NUMS, [1, 2, 10, 33]);
+   assert_eq!(INDICES, ["0a", "1b", "2c"]);
+   assert_eq!(RANGE, [0, 1, 2]);
+}
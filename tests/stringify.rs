@@ -0,0 +1,165 @@
+use template_macro::template;
+
+// spacing compared to rustc's `stringify!` of rustfmt formatted code
+template!{
+   pub const S0: &str = $stringify{ a & b };
+   pub const S1: &str = $stringify{ a * b - c };
+   pub const S2: &str = $stringify{ -1 };
+   pub const S3: &str = $stringify{ f(-1, -x) };
+   pub const S4: &str = $stringify{ x == -1 };
+   pub const S5: &str = $stringify{ a - -1 };
+   pub const S6: &str = $stringify{ *x = -1; };
+   pub const S7: &str = $stringify{ return -x; };
+   pub const S8: &str = $stringify{ **x };
+   pub const S9: &str = $stringify{ &*x };
+   pub const S10: &str = $stringify{ &mut v };
+   pub const S11: &str = $stringify{ &&x };
+   pub const S12: &str = $stringify{ a && b };
+   pub const S13: &str = $stringify{ !done };
+   pub const S14: &str = $stringify{ if !a.is_empty() {} };
+   pub const S15: &str = $stringify{ println!("{}", x) };
+   pub const S16: &str = $stringify{ |x| x + 1 };
+   pub const S17: &str = $stringify{ move |a, b| a * b };
+   pub const S18: &str = $stringify{ || 42 };
+   pub const S19: &str = $stringify{ let f = |x: &u8| -> u8 { *x }; };
+   pub const S20: &str = $stringify{ a | b };
+   pub const S21: &str = $stringify{ Some(a) | None => {} };
+   pub const S22: &str = $stringify{ a < b && c > d };
+   pub const S23: &str = $stringify{ f(a < b, c > d) };
+   pub const S24: &str = $stringify{ a < b || c > d };
+   pub const S25: &str = $stringify{ if a < b { c } else { d } };
+   pub const S26: &str = $stringify{ 1 < x };
+   pub const S27: &str = $stringify{ x as u8 };
+   pub const S28: &str = $stringify{ x as *const u8 };
+   pub const S29: &str = $stringify{ fn f() -> *const u8 {} };
+   pub const S30: &str = $stringify{ T: ?Sized + Clone };
+   pub const S31: &str = $stringify{ x?.y()? };
+   pub const S32: &str = $stringify{ Vec::<u8>::new() };
+   pub const S33: &str = $stringify{ <T as Trait>::f() };
+   pub const S34: &str = $stringify{ Ok::<u8, ()>(1) };
+   pub const S35: &str = $stringify{ impl<T> From<T> for U {} };
+   pub const S36: &str = $stringify{ Box<dyn Fn(&u8) -> bool + Send> };
+   pub const S37: &str = $stringify{ where T: Fn(u8) -> u8, };
+   pub const S38: &str = $stringify{ &'static str };
+   pub const S39: &str = $stringify{ &x[..2] };
+   pub const S40: &str = $stringify{ x[1..] };
+   pub const S41: &str = $stringify{ a.. };
+   pub const S42: &str = $stringify{ ..b };
+   pub const S43: &str = $stringify{ #[derive(Debug)] };
+   pub const S44: &str = $stringify{ #![allow(unused)] };
+   pub const S45: &str = $stringify{ 'a: loop { break 'a; } };
+   pub const S46: &str = $stringify{ Some(x) if x > 0 => x, };
+   pub const S47: &str = $stringify{ use a::{b, c::*}; };
+   pub const S48: &str = $stringify{ let Foo { a, .. } = f; };
+   pub const S49: &str = $stringify{ pub(crate) fn f(&self) {} };
+   pub const S50: &str = $stringify{ x = y + z * w; };
+   pub const S51: &str = $stringify{ 1u8 << 2 };
+   pub const S52: &str = $stringify{ a ^ b % c };
+   pub const S53: &str = $stringify{ -x.abs() };
+   pub const S54: &str = $stringify{ for &x in xs {} };
+   pub const S55: &str = $stringify{ async move {} };
+   pub const S56: &str = $stringify{ [0; 4] };
+   pub const S57: &str = $stringify{ a = &b; };
+   pub const S58: &str = $stringify{ match x { A => -1, _ => !0 } };
+   pub const S59: &str = $stringify{ &[u8] };
+   pub const S60: &str = $stringify{ *mut T };
+   pub const S61: &str = $stringify{ Vec<&'a T> };
+   pub const S62: &str = $stringify{ impl Trait for &T {} };
+   pub const S63: &str = $stringify{ fn f<T: Into<u8>>(x: T) {} };
+   pub const S64: &str = $stringify{ let x: [u8; 3] = [1, 2, 3]; };
+   pub const S65: &str = $stringify{ x += -1; };
+   pub const S66: &str = $stringify{ (a, -b) };
+   pub const S67: &str = $stringify{ if a > b && c < d {} };
+   pub const S68: &str = $stringify{ map(|(k, v)| k) };
+   pub const S69: &str = $stringify{ xs.iter().map(|x| x * 2) };
+   pub const S70: &str = $stringify{ x.len() < 3 };
+   pub const S71: &str = $stringify{ HashMap<K, V> };
+   pub const S72: &str = $stringify{ struct S<T>(T); };
+   pub const S73: &str = $stringify{ enum E { A = -1 } };
+   pub const S74: &str = $stringify{ let v = vec![-1, 2]; };
+   pub const S75: &str = $stringify{ x.y::<u8>() };
+   pub const S76: &str = $stringify{ T: for<'a> Fn(&'a u8) };
+   pub const S77: &str = $stringify{ a - b };
+}
+
+#[test]
+fn stringify() {
+   assert_eq!(S0, stringify!(a & b));
+   assert_eq!(S1, stringify!(a * b - c));
+   assert_eq!(S2, stringify!(-1));
+   assert_eq!(S3, stringify!(f(-1, -x)));
+   assert_eq!(S4, stringify!(x == -1));
+   assert_eq!(S5, stringify!(a - -1));
+   assert_eq!(S6, stringify!(*x = -1;));
+   assert_eq!(S7, stringify!(return -x;));
+   assert_eq!(S8, stringify!(**x));
+   assert_eq!(S9, stringify!(&*x));
+   assert_eq!(S10, stringify!(&mut v));
+   assert_eq!(S11, stringify!(&&x));
+   assert_eq!(S12, stringify!(a && b));
+   assert_eq!(S13, stringify!(!done));
+   assert_eq!(S14, stringify!(if !a.is_empty() {}));
+   assert_eq!(S15, stringify!(println!("{}", x)));
+   assert_eq!(S16, stringify!(|x| x + 1));
+   assert_eq!(S17, stringify!(move |a, b| a * b));
+   assert_eq!(S18, stringify!(|| 42));
+   assert_eq!(S19, stringify!(let f = |x: &u8| -> u8 { *x };));
+   assert_eq!(S20, stringify!(a | b));
+   assert_eq!(S21, stringify!(Some(a) | None => {}));
+   assert_eq!(S22, stringify!(a < b && c > d));
+   assert_eq!(S23, stringify!(f(a < b, c > d)));
+   assert_eq!(S24, stringify!(a < b || c > d));
+   assert_eq!(S25, stringify!(if a < b { c } else { d }));
+   assert_eq!(S26, stringify!(1 < x));
+   assert_eq!(S27, stringify!(x as u8));
+   assert_eq!(S28, stringify!(x as *const u8));
+   assert_eq!(S29, stringify!(fn f() -> *const u8 {}));
+   assert_eq!(S30, stringify!(T: ?Sized + Clone));
+   assert_eq!(S31, stringify!(x?.y()?));
+   assert_eq!(S32, stringify!(Vec::<u8>::new()));
+   assert_eq!(S33, stringify!(<T as Trait>::f()));
+   assert_eq!(S34, stringify!(Ok::<u8, ()>(1)));
+   assert_eq!(S35, stringify!(impl<T> From<T> for U {}));
+   assert_eq!(S36, stringify!(Box<dyn Fn(&u8) -> bool + Send>));
+   assert_eq!(S37, stringify!(where T: Fn(u8) -> u8,));
+   assert_eq!(S38, stringify!(&'static str));
+   assert_eq!(S39, stringify!(&x[..2]));
+   assert_eq!(S40, stringify!(x[1..]));
+   assert_eq!(S41, stringify!(a..));
+   assert_eq!(S42, stringify!(..b));
+   assert_eq!(S43, stringify!(#[derive(Debug)]));
+   assert_eq!(S44, stringify!(#![allow(unused)]));
+   assert_eq!(S45, stringify!('a: loop { break 'a; }));
+   assert_eq!(S46, stringify!(Some(x) if x > 0 => x,));
+   assert_eq!(S47, stringify!(use a::{b, c::*};));
+   assert_eq!(S48, stringify!(let Foo { a, .. } = f;));
+   assert_eq!(S49, stringify!(pub(crate) fn f(&self) {}));
+   assert_eq!(S50, stringify!(x = y + z * w;));
+   assert_eq!(S51, stringify!(1u8 << 2));
+   assert_eq!(S52, stringify!(a ^ b % c));
+   assert_eq!(S53, stringify!(-x.abs()));
+   assert_eq!(S54, stringify!(for &x in xs {}));
+   assert_eq!(S55, stringify!(async move {}));
+   assert_eq!(S56, stringify!([0; 4]));
+   assert_eq!(S57, stringify!(a = &b;));
+   assert_eq!(S58, stringify!(match x { A => -1, _ => !0 }));
+   assert_eq!(S59, stringify!(&[u8]));
+   assert_eq!(S60, stringify!(*mut T));
+   assert_eq!(S61, stringify!(Vec<&'a T>));
+   assert_eq!(S62, stringify!(impl Trait for &T {}));
+   assert_eq!(S63, stringify!(fn f<T: Into<u8>>(x: T) {}));
+   assert_eq!(S64, stringify!(let x: [u8; 3] = [1, 2, 3];));
+   assert_eq!(S65, stringify!(x += -1;));
+   assert_eq!(S66, stringify!((a, -b)));
+   assert_eq!(S67, stringify!(if a > b && c < d {}));
+   assert_eq!(S68, stringify!(map(|(k, v)| k)));
+   assert_eq!(S69, stringify!(xs.iter().map(|x| x * 2)));
+   assert_eq!(S70, stringify!(x.len() < 3));
+   assert_eq!(S71, stringify!(HashMap<K, V>));
+   assert_eq!(S72, stringify!(struct S<T>(T);));
+   assert_eq!(S73, stringify!(enum E { A = -1 }));
+   assert_eq!(S74, stringify!(let v = vec![-1, 2];));
+   assert_eq!(S75, stringify!(x.y::<u8>()));
+   assert_eq!(S76, stringify!(T: for<'a> Fn(&'a u8)));
+   assert_eq!(S77, stringify!(a - b));
+}
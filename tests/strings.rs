@@ -0,0 +1,50 @@
+use template_macro::template;
+
+template!{
+   $name: foo
+   $ty: {{ Vec<Option<u8>> }}
+   pub const A: &str = $str{"Returns the " $(name) " field" ' ' 1};
+   pub const B: &str = $raw_str{"quoted" $(name) "\"#"};
+   pub const C: &[u8] = $byte_str{"ab" $(name)};
+   pub const D: &str = $stringify(ty);
+   pub const E: &str = $stringify{ fn $(name)(x: u8) -> $(ty) {} };
+   pub const F: &str = $stringify(name);
+   $field: r#type
+   #[doc = $str{"the " $(field) " field"}]
+   pub const G: &str = $str{$(field)};
+   pub const H: &str = $raw_str{r#match "_" $(field)};
+   #[doc = $str{"doc for " $(name)}]
+   pub struct Documented;
+
+   // compared to rustc's `stringify!`
+   pub const S1: &str = $stringify{ x.y()?; a < b && c > d; };
+   pub const S2: &str = $stringify{ let v: Vec<Option<u8>> = Vec::new(); };
+   pub const S3: &str = $stringify{ a += 1; b <<= 2; c >>= 3; d != e; };
+   pub const S4: &str = $stringify{ impl<'a, T: Clone> Foo<'a, T> for &'a Bar where T: Copy {} };
+   pub const S5: &str = $stringify{ match x { 1..=5 => f::<u8>(), _ => {} } };
+   pub const S6: &str = $stringify{ if a <= b || c >= d { x..y } };
+   pub const S7: &str = $stringify{ a < b; };
+   pub const S9: &str = $stringify{ <T, U: A<B>> };
+   pub const S8: &str = $stringify{ fn f(s: &'a str) -> Result<(), Box<dyn Fn(u8) -> u8>> { Foo { a, ..b } } };
+}
+
+#[test]
+fn strings() {
+   assert_eq!(A, "Returns the foo field 1");
+   assert_eq!(B, "quotedfoo\"#");
+   assert_eq!(C, b"abfoo");
+   assert_eq!(D, stringify!(Vec<Option<u8>>));
+   assert_eq!(E, stringify!(fn foo(x: u8) -> Vec<Option<u8>> {}));
+   assert_eq!(F, "foo");
+   assert_eq!(G, "type");
+   assert_eq!(H, "match_type");
+   assert_eq!(S1, stringify!( x.y()?; a < b && c > d; ));
+   assert_eq!(S2, stringify!( let v: Vec<Option<u8>> = Vec::new(); ));
+   assert_eq!(S3, stringify!( a += 1; b <<= 2; c >>= 3; d != e; ));
+   assert_eq!(S4, stringify!( impl<'a, T: Clone> Foo<'a, T> for &'a Bar where T: Copy {} ));
+   assert_eq!(S5, stringify!( match x { 1..=5 => f::<u8>(), _ => {} } ));
+   assert_eq!(S6, stringify!( if a <= b || c >= d { x..y } ));
+   assert_eq!(S7, stringify!( a < b; ));
+   assert_eq!(S9, stringify!( <T, U: A<B>> ));
+   assert_eq!(S8, stringify!( fn f(s: &'a str) -> Result<(), Box<dyn Fn(u8) -> u8>> { Foo { a, ..b } } ));
+}
@@ -0,0 +1,41 @@
+use template_macro::template;
+
+template!{
+   $nums: (3, 1, 2, 3, 1)
+   pub const SORTED: [u8; 5] = [$[@sort(nums)]{ $(@value) } sep {,}];
+   pub const UNIQUE: [u8; 3] = [$[@unique(nums)]{ $(@value) } sep {,}];
+   $fields: ({ name: b, size: 2 }, { name: a, size: 10 }, { name: c, size: 1 })
+   pub const BY_SIZE: [&str; 3] = [$[f in @sort_by_key(fields, @value.size)]{ $str{$(f.name)} } sep {,}];
+   pub const FLAT: [u8; 5] = [$[@flatten(((1, 2), 3, (4, 5)))]{ $(@value) } sep {,}];
+   $m: { x: 1, y: 2 }
+   $n: { y: 20, z: 30 }
+   pub const KEYS: [&str; 2] = [$[k in @keys(m)]{ $str{$(k)} } sep {,}];
+   pub const VALUES: [u8; 2] = [$[@values(m)]{ $(@value) } sep {,}];
+   pub const ENTRIES: [(&str, u8); 2] = [$[(k, v) in @entries(m)]{ ($str{$(k)}, $(v)) } sep {,}];
+   $merged: @merge(m, n)
+   pub const MERGED: [u8; 3] = [$[@values(merged)]{ $(@value) } sep {,}];
+   pub const CONCAT: usize = $len(@concat(nums, (7, 8), @range(0..2)));
+   $groups: @group_by(({ k: a, v: 1 }, { k: b, v: 2 }, { k: a, v: 3 }), @value.k)
+   pub const GROUPS: [(&str, usize); 2] = [$[(k, g) in groups]{ ($str{$(k)}, $len(g)) } sep {,}];
+   pub const REV_SORTED: [&str; 3] = [$[f in @reverse(@sort_by_key(fields, @value.name))]{ $str{$(f.name)} } sep {,}];
+   $repeated: { a: 1, b: 2, a: 3 }
+   pub const REPEATED: [u8; 2] = [$[@values(repeated)]{ $(@value) } sep {,}];
+   pub const SORTED_MAP: [&str; 2] = [$[@sort({ b: 1, a: 2 })]{ $str{$(@key)} } sep {,}];
+}
+
+#[test]
+fn transform() {
+   assert_eq!(SORTED, [1, 1, 2, 3, 3]);
+   assert_eq!(UNIQUE, [3, 1, 2]);
+   assert_eq!(BY_SIZE, ["c", "b", "a"]);
+   assert_eq!(FLAT, [1, 2, 3, 4, 5]);
+   assert_eq!(KEYS, ["x", "y"]);
+   assert_eq!(VALUES, [1, 2]);
+   assert_eq!(ENTRIES, [("x", 1), ("y", 2)]);
+   assert_eq!(MERGED, [1, 20, 30]);
+   assert_eq!(CONCAT, 9);
+   assert_eq!(GROUPS, [("a", 2), ("b", 1)]);
+   assert_eq!(REV_SORTED, ["c", "b", "a"]);
+   assert_eq!(REPEATED, [3, 2]);
+   assert_eq!(SORTED_MAP, ["a", "b"]);
+}